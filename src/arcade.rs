@@ -0,0 +1,117 @@
+//! Timed arcade rules: as many journeys as possible before the clock runs out.
+
+use crate::components::{Collided, Journey, JourneyFinished, OnArrival, Transition};
+use crate::game_state::{Game, GameState};
+use crate::replays::Playback;
+use crate::simulation::Rules;
+use bevy::prelude::*;
+
+/// Seconds given at the start of a game.
+pub const ARCADE_SECONDS: f32 = 60.;
+/// Seconds added by each finished journey.
+const JOURNEY_SECONDS: f32 = 5.;
+/// Seconds lost each time the player is hit.
+const COLLISION_SECONDS: f32 = 5.;
+
+/// Time left in the current arcade game.
+#[derive(Debug, Resource)]
+pub struct ArcadeClock {
+    pub remaining: f32,
+}
+
+impl Default for ArcadeClock {
+    fn default() -> Self {
+        Self {
+            remaining: ARCADE_SECONDS,
+        }
+    }
+}
+
+pub fn reset_arcade_clock(mut clock: ResMut<ArcadeClock>) {
+    *clock = ArcadeClock::default();
+}
+
+/// Counts down while the game runs, and ends it when the time is up.
+pub fn tick_arcade_clock(
+    mut commands: Commands,
+    mut clock: ResMut<ArcadeClock>,
+    mut game: ResMut<Game>,
+    time: Res<Time>,
+    arrivals: Query<Entity, With<OnArrival>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if game.rules != Rules::Arcade || game.game_over {
+        return;
+    }
+    clock.remaining = (clock.remaining - time.delta_seconds()).max(0.);
+    if clock.remaining == 0. {
+        end_arcade_game(&mut commands, &mut game, &arrivals, &mut next_state);
+    }
+}
+
+/// Replays do not keep when each move was played, so their arcade games end
+/// once their last move is played instead of on time.
+pub fn end_arcade_replay(
+    mut commands: Commands,
+    playback: Res<Playback>,
+    mut game: ResMut<Game>,
+    moving: Query<(), With<Transition>>,
+    arrivals: Query<Entity, With<OnArrival>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if game.rules != Rules::Arcade
+        || game.game_over
+        || playback.cursor < playback.replay.moves.len()
+        || !moving.is_empty()
+    {
+        return;
+    }
+    end_arcade_game(&mut commands, &mut game, &arrivals, &mut next_state);
+}
+
+/// Moves still playing end silently: a journey finished on the last move
+/// does not bring the next player and its coin on the end screen.
+fn end_arcade_game(
+    commands: &mut Commands,
+    game: &mut Game,
+    arrivals: &Query<Entity, With<OnArrival>>,
+    next_state: &mut NextState<GameState>,
+) {
+    game.game_over = true;
+    for entity in arrivals.iter() {
+        commands.entity(entity).remove::<OnArrival>();
+    }
+    next_state.set(GameState::EndGame);
+}
+
+pub fn add_journey_seconds(
+    _trigger: Trigger<JourneyFinished>,
+    mut clock: ResMut<ArcadeClock>,
+    game: Res<Game>,
+) {
+    if game.rules == Rules::Arcade && !game.game_over {
+        clock.remaining += JOURNEY_SECONDS;
+    }
+}
+
+/// Only the player loses time, bots hit by it do not count.
+pub fn remove_collision_seconds(
+    trigger: Trigger<Collided>,
+    mut clock: ResMut<ArcadeClock>,
+    journeys: Query<&Journey>,
+    game: Res<Game>,
+) {
+    if game.rules != Rules::Arcade || game.game_over {
+        return;
+    }
+    let Ok(journey) = journeys.get(trigger.entity()) else {
+        return;
+    };
+    if game
+        .player
+        .as_ref()
+        .is_some_and(|player| player.id == journey.id)
+    {
+        clock.remaining = (clock.remaining - COLLISION_SECONDS).max(0.);
+    }
+}
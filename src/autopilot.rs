@@ -0,0 +1,256 @@
+//! A player playing by itself, used for the attract-mode demo and to measure
+//! how hard a board is.
+
+use crate::game_state::GameState;
+use crate::grid::GridConfig;
+use crate::levels::Level;
+use crate::routes::{TimeGrid, DIRECTIONS};
+use crate::simulation::{Simulation, WAIT};
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+/// Seconds without any input on the end screen before the demo starts.
+const IDLE_DELAY: f32 = 20.;
+
+/// The demo should lose once in a while instead of playing forever.
+const DEMO_STRENGTH: Strength = Strength::Careful;
+
+/// Moves after which a measured game is stopped, whatever its state.
+const MAX_MEASURED_TURNS: u32 = 5_000;
+
+/// How well the autopilot plays, from the weakest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Strength {
+    /// Any direction that is not blocked.
+    Random,
+    /// Straight to the target, then back to the start, ignoring the bots.
+    Greedy,
+    /// Like [`Strength::Greedy`], but never moves where a bot will be next turn.
+    Careful,
+    /// Follows a safe shortest route through every upcoming turn.
+    #[default]
+    Lookahead,
+}
+
+impl Strength {
+    pub const ALL: [Self; 4] = [Self::Random, Self::Greedy, Self::Careful, Self::Lookahead];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Random => "random",
+            Self::Greedy => "greedy",
+            Self::Careful => "careful",
+            Self::Lookahead => "lookahead",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|strength| strength.name() == name)
+            .ok_or(format!("Unknown autopilot strength '{name}'"))
+    }
+}
+
+/// Next direction of the player at the given strength, none if it cannot move.
+pub fn choose_direction(
+    simulation: &Simulation,
+    strength: Strength,
+    rng: &mut impl Rng,
+) -> Option<IVec2> {
+    let player = simulation.player.as_ref()?;
+    let possible: Vec<IVec2> = DIRECTIONS
+        .into_iter()
+        .filter(|&direction| {
+            let next_pos = player.pos + direction;
+            next_pos.cmpge(IVec2::ZERO).all()
+                && next_pos.cmplt(simulation.grid_size.as_ivec2()).all()
+                && !simulation.walls.contains(&next_pos)
+        })
+        .collect();
+
+    let goal = if player.has_reached_target() || player.pos == player.target_pos {
+        player.start_pos
+    } else {
+        player.target_pos
+    };
+
+    match strength {
+        Strength::Random => possible.choose(rng).copied(),
+        Strength::Greedy => toward(goal, player.pos, &possible, rng),
+        Strength::Careful => {
+            // One turn is enough to tell which moves are safe right now
+            let next_turn = TimeGrid::with_max_turns(simulation, 1);
+            let safe: Vec<IVec2> = possible
+                .iter()
+                .copied()
+                .chain([WAIT])
+                .filter(|&direction| next_turn.is_safe_first_move(player, direction))
+                .collect();
+            toward(goal, player.pos, &safe, rng).or_else(|| possible.choose(rng).copied())
+        }
+        Strength::Lookahead => TimeGrid::new(simulation)
+            .next_safe_move(player)
+            .or_else(|| possible.choose(rng).copied()),
+    }
+}
+
+/// One of the `directions` getting closer to `goal`, any of them otherwise.
+fn toward(goal: IVec2, pos: IVec2, directions: &[IVec2], rng: &mut impl Rng) -> Option<IVec2> {
+    let distance = |pos: IVec2| (goal - pos).abs().element_sum();
+    let closer: Vec<IVec2> = directions
+        .iter()
+        .copied()
+        .filter(|&direction| distance(pos + direction) < distance(pos))
+        .collect();
+    closer.choose(rng).or(directions.choose(rng)).copied()
+}
+
+/// Plays a whole game on `level` and returns the number of journeys made.
+pub fn play_game(level: &Level, strength: Strength, seed: u64) -> u32 {
+    let mut game_rng = StdRng::seed_from_u64(seed);
+    let mut autopilot_rng = StdRng::seed_from_u64(seed);
+    let mut simulation = Simulation::new(level);
+    simulation.start(&mut game_rng);
+
+    while !simulation.game_over && simulation.turn < MAX_MEASURED_TURNS {
+        let Some(direction) = choose_direction(&simulation, strength, &mut autopilot_rng) else {
+            break;
+        };
+        simulation.step(direction, &mut game_rng);
+    }
+    simulation.score.nb_journeys
+}
+
+/// Prints the average number of journeys of each strength over `games` games.
+pub fn measure_difficulty(level: &Level, games: u64) {
+    println!("Journeys per game over {games} games:");
+    for strength in Strength::ALL {
+        let journeys: u32 = (0..games)
+            .map(|seed| play_game(level, strength, seed))
+            .sum();
+        println!(
+            "{:>10}: {:.1}",
+            strength.name(),
+            journeys as f32 / games.max(1) as f32
+        );
+    }
+}
+
+/// Plays instead of the player when enabled, or during the demo.
+#[derive(Debug, Resource)]
+pub struct Autopilot {
+    pub strength: Strength,
+    pub enabled: bool,
+    rng: StdRng,
+}
+
+impl Default for Autopilot {
+    fn default() -> Self {
+        Self::new(Strength::default(), false)
+    }
+}
+
+impl Autopilot {
+    pub fn new(strength: Strength, enabled: bool) -> Self {
+        Self {
+            strength,
+            enabled,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    pub fn choose(&mut self, simulation: &Simulation, demo: bool) -> Option<IVec2> {
+        let strength = if demo { DEMO_STRENGTH } else { self.strength };
+        choose_direction(simulation, strength, &mut self.rng)
+    }
+}
+
+/// The current game is the attract-mode demo, played by the autopilot.
+#[derive(Debug, Resource)]
+pub struct Demo;
+
+pub fn toggle_autopilot(keyboard: Res<ButtonInput<KeyCode>>, mut autopilot: ResMut<Autopilot>) {
+    if keyboard.just_pressed(KeyCode::Tab) {
+        autopilot.enabled = !autopilot.enabled;
+    }
+}
+
+pub fn start_demo_when_idle(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    touches: Res<Touches>,
+    time: Res<Time>,
+    mut idle_time: Local<f32>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard.get_pressed().next().is_some() || touches.iter().next().is_some() {
+        *idle_time = 0.;
+        return;
+    }
+    *idle_time += time.delta_seconds();
+    if *idle_time >= IDLE_DELAY {
+        *idle_time = 0.;
+        commands.insert_resource(Demo);
+        next_state.set(GameState::InGame);
+    }
+}
+
+pub fn stop_demo_on_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    touches: Res<Touches>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard.get_just_pressed().next().is_some() || touches.any_just_pressed() {
+        next_state.set(GameState::EndGame);
+    }
+}
+
+pub fn stop_demo(mut commands: Commands) {
+    commands.remove_resource::<Demo>();
+}
+
+#[derive(Debug, Component)]
+pub struct AutopilotDisplay;
+
+fn autopilot_text(autopilot: &Autopilot, demo: bool) -> String {
+    if demo {
+        "Demo\nPress any key".to_string()
+    } else if autopilot.enabled {
+        format!("Autopilot ({})\nTab: stop", autopilot.strength.name())
+    } else {
+        String::new()
+    }
+}
+
+pub fn spawn_autopilot_display(mut commands: Commands, grid: Res<GridConfig>) {
+    let text_position = Vec3::new(0., -(grid.window_size / 2. - 20.), 0.);
+
+    commands.spawn((
+        AutopilotDisplay,
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 14.0,
+                    ..default()
+                },
+            )
+            .with_justify(JustifyText::Center),
+            transform: Transform::from_translation(text_position),
+            ..default()
+        },
+    ));
+}
+
+pub fn update_autopilot_display(
+    mut displays: Query<&mut Text, With<AutopilotDisplay>>,
+    autopilot: Res<Autopilot>,
+    demo: Option<Res<Demo>>,
+) {
+    for mut text in displays.iter_mut() {
+        text.sections[0].value = autopilot_text(&autopilot, demo.is_some());
+    }
+}
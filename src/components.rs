@@ -1,4 +1,6 @@
+use crate::simulation::{BotBehavior, Character, CharacterId};
 use bevy::prelude::*;
+use std::collections::VecDeque;
 
 #[derive(Debug, Component)]
 pub struct GameObject;
@@ -11,6 +13,10 @@ pub struct Transition {
     pub start: Position,
     pub end: Position,
     pub current: Vec2,
+    /// Part of the way actually travelled, a head-on collision stops halfway.
+    pub length: f32,
+    /// Cells travelled so far. Waiting in place takes as long as a move.
+    pub travelled: f32,
 }
 
 impl Transition {
@@ -19,6 +25,15 @@ impl Transition {
             start,
             end,
             current: start.0.as_vec2(),
+            length: 1.,
+            travelled: 0.,
+        }
+    }
+
+    pub fn head_on(self) -> Self {
+        Self {
+            length: 0.5,
+            ..self
         }
     }
 }
@@ -26,8 +41,9 @@ impl Transition {
 #[derive(Debug, Component)]
 pub struct Player;
 
-#[derive(Debug, Component)]
-pub struct JustSpawned;
+/// Directions pressed during a transition, played one by one when it ends.
+#[derive(Debug, Component, Default)]
+pub struct InputQueue(pub VecDeque<IVec2>);
 
 #[derive(Debug, Component)]
 pub struct GameFinishedPlayer;
@@ -37,19 +53,54 @@ pub struct Automated;
 
 pub type WithPlayerOrAutomated = Or<(With<Automated>, With<Player>)>;
 
+/// Outcome already decided by the simulation, played when the transition ends.
+#[derive(Debug, Component, Clone, Copy, PartialEq, Eq)]
+pub enum OnArrival {
+    Collides,
+    ReachesTarget,
+    FinishesJourney,
+}
+
 #[derive(Debug, Component)]
 pub struct Destroyed;
 
+/// Mirror of a simulation [`Character`], updated after each step.
 #[derive(Debug, Component)]
 pub struct Journey {
+    pub id: CharacterId,
     pub start_pos: Position,
     pub target_pos: Position,
     pub path: Vec<Position>,
-    pub bot_index: i32,
+    pub bot_index: usize,
+    pub behavior: BotBehavior,
     // display
     pub color: Color,
     pub scale: f32,
 }
 
+impl Journey {
+    pub fn new(character: &Character, color: Color) -> Self {
+        Self {
+            id: character.id,
+            start_pos: Position(character.start_pos),
+            target_pos: Position(character.target_pos),
+            path: character.path.iter().copied().map(Position).collect(),
+            bot_index: character.bot_index,
+            behavior: character.behavior,
+            color,
+            scale: character.scale,
+        }
+    }
+
+    pub fn update(&mut self, character: &Character) {
+        self.path = character.path.iter().copied().map(Position).collect();
+        self.bot_index = character.bot_index;
+        self.behavior = character.behavior;
+    }
+}
+
 #[derive(Event)]
 pub struct JourneyFinished;
+
+#[derive(Event)]
+pub struct Collided;
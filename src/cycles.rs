@@ -0,0 +1,287 @@
+//! When the board repeats itself, and where the bots are on any turn.
+//!
+//! Each bot replays its path with the period of its behavior, so the whole
+//! board repeats with the least common multiple of these periods, once the
+//! parking bots are parked. Like [`crate::simulation`], only plain Rust and
+//! Bevy's math types are used here.
+//!
+//! Bots sending each other back to their start are not foreseen: this resets
+//! their phase and breaks the cycle. [`crate::routes::TimeGrid`] plays the
+//! bots turn by turn when routes must be exact.
+
+use crate::simulation::{BotBehavior, Character};
+use bevy::math::IVec2;
+use std::collections::{HashMap, HashSet};
+
+/// Cells covered by a bot on each phase of its behavior.
+#[derive(Debug, Clone)]
+struct BotCycle {
+    behavior: BotBehavior,
+    /// Phase of the bot on turn 0.
+    phase: usize,
+    len: usize,
+    cells: Vec<IVec2>,
+}
+
+impl BotCycle {
+    fn new(bot: &Character) -> Self {
+        let len = bot.path.len().max(1);
+        let cells = (0..bot.behavior.period(len))
+            .map(|phase| {
+                let index = bot.behavior.path_index(phase, len);
+                bot.path.get(index).copied().unwrap_or(bot.start_pos)
+            })
+            .collect();
+        Self {
+            behavior: bot.behavior,
+            phase: bot.bot_phase,
+            len,
+            cells,
+        }
+    }
+
+    fn cell(&self, turn: usize) -> IVec2 {
+        self.cells[self.behavior.phase_after(self.phase, turn, self.len)]
+    }
+
+    /// Turns before the bot moves the same way forever.
+    fn transient(&self) -> usize {
+        match self.behavior {
+            BotBehavior::Once => self.len.saturating_sub(self.phase),
+            _ => 0,
+        }
+    }
+
+    /// Turns after which the bot is back on the same phase, once repeating.
+    fn period(&self) -> usize {
+        match self.behavior {
+            BotBehavior::Once => 1,
+            behavior => behavior.period(self.len),
+        }
+    }
+
+    /// Turns spent on each cell over one period of the repeating part.
+    fn time_per_cell(&self) -> HashMap<IVec2, usize> {
+        let mut times = HashMap::new();
+        for turn in self.transient()..self.transient() + self.period() {
+            *times.entry(self.cell(turn)).or_default() += 1;
+        }
+        times
+    }
+}
+
+/// Where the bots of a board are on each turn from now, turn 0 being the
+/// current positions.
+#[derive(Debug, Clone)]
+pub struct Cycles {
+    bots: Vec<BotCycle>,
+}
+
+impl Cycles {
+    pub fn new(bots: &[Character]) -> Self {
+        Self {
+            bots: bots.iter().map(BotCycle::new).collect(),
+        }
+    }
+
+    /// Turns before the board starts repeating, while parking bots finish
+    /// their loop.
+    pub fn transient(&self) -> usize {
+        self.bots.iter().map(BotCycle::transient).max().unwrap_or(0)
+    }
+
+    /// Turns after which the board is the same again, once repeating. `None`
+    /// if it does not fit in a `usize`.
+    pub fn period(&self) -> Option<usize> {
+        self.bots
+            .iter()
+            .try_fold(1, |period, bot| lcm(period, bot.period()))
+    }
+
+    /// First turn showing the same board as an earlier one, `period` turns
+    /// before.
+    pub fn first_repeat(&self) -> Option<usize> {
+        self.transient().checked_add(self.period()?)
+    }
+
+    pub fn is_occupied(&self, cell: IVec2, turn: usize) -> bool {
+        self.bots.iter().any(|bot| bot.cell(turn) == cell)
+    }
+
+    pub fn occupied_cells(&self, turn: usize) -> HashSet<IVec2> {
+        self.bots.iter().map(|bot| bot.cell(turn)).collect()
+    }
+
+    /// Average number of bots on each cell once the board repeats, cells never
+    /// covered being left out.
+    pub fn busyness(&self) -> HashMap<IVec2, f32> {
+        let mut busyness = HashMap::new();
+        for bot in self.bots.iter() {
+            let period = bot.period() as f32;
+            for (cell, time) in bot.time_per_cell() {
+                *busyness.entry(cell).or_default() += time as f32 / period;
+            }
+        }
+        busyness
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn lcm(a: usize, b: usize) -> Option<usize> {
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::levels::Level;
+    use crate::simulation::{step_bots, Simulation};
+    use bevy::math::UVec2;
+
+    const BEHAVIORS: [BotBehavior; 4] = [
+        BotBehavior::Loop,
+        BotBehavior::PingPong,
+        BotBehavior::Once,
+        BotBehavior::EveryNth(3),
+    ];
+
+    fn square() -> Vec<IVec2> {
+        vec![
+            IVec2::new(0, 0),
+            IVec2::new(1, 0),
+            IVec2::new(1, 1),
+            IVec2::new(0, 1),
+        ]
+    }
+
+    /// Bot on `path` at `phase` of `behavior`, as the simulation places it.
+    fn bot(path: Vec<IVec2>, behavior: BotBehavior, phase: usize) -> Character {
+        let level = Level {
+            bots: vec![path],
+            ..Level::empty(UVec2::new(4, 4))
+        };
+        let mut bot = Simulation::new(&level).bots.remove(0);
+        let len = bot.path.len();
+        bot.behavior = behavior;
+        bot.bot_phase = phase;
+        bot.bot_index = behavior.path_index(phase, len);
+        bot.pos = bot.path[bot.bot_index];
+        bot
+    }
+
+    #[test]
+    fn cells_follow_the_bots_turn_by_turn() {
+        for behavior in BEHAVIORS {
+            for phase in 0..behavior.period(4) {
+                let mut bots = vec![bot(square(), behavior, phase)];
+                let cycles = Cycles::new(&bots);
+                for turn in 0..3 * behavior.period(4) {
+                    let pos = bots[0].pos;
+                    assert!(
+                        cycles.is_occupied(pos, turn),
+                        "{behavior:?} from phase {phase}, turn {turn}"
+                    );
+                    assert_eq!(cycles.occupied_cells(turn), HashSet::from([pos]));
+                    step_bots(&mut bots);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn period_of_each_behavior() {
+        let period = |behavior| Cycles::new(&[bot(square(), behavior, 0)]).period();
+        assert_eq!(period(BotBehavior::Loop), Some(4));
+        assert_eq!(period(BotBehavior::PingPong), Some(6));
+        assert_eq!(period(BotBehavior::Once), Some(1));
+        assert_eq!(period(BotBehavior::EveryNth(3)), Some(12));
+
+        let single = vec![IVec2::new(2, 2)];
+        for behavior in BEHAVIORS {
+            let cycles = Cycles::new(&[bot(single.clone(), behavior, 0)]);
+            assert_eq!(cycles.occupied_cells(5), HashSet::from([single[0]]));
+        }
+        assert_eq!(Cycles::new(&[]).period(), Some(1));
+    }
+
+    #[test]
+    fn parked_bot_stays_on_its_start() {
+        let path = vec![IVec2::new(0, 0), IVec2::new(1, 0), IVec2::new(2, 0)];
+        let cycles = Cycles::new(&[bot(path.clone(), BotBehavior::Once, 0)]);
+
+        assert_eq!(cycles.transient(), 3);
+        assert_eq!(cycles.first_repeat(), Some(4));
+        for (index, turn) in [0, 1, 2, 0, 0, 0].into_iter().zip(0..) {
+            assert!(cycles.is_occupied(path[index], turn));
+        }
+
+        let parked = Cycles::new(&[bot(path.clone(), BotBehavior::Once, 3)]);
+        assert_eq!(parked.transient(), 0);
+        assert_eq!(parked.first_repeat(), Some(1));
+        assert!(parked.is_occupied(path[0], 0) && parked.is_occupied(path[0], 10));
+    }
+
+    #[test]
+    fn board_repeats_from_first_repeat() {
+        let bots: Vec<Character> = BEHAVIORS
+            .into_iter()
+            .zip(0..)
+            .map(|(behavior, phase)| bot(square(), behavior, phase))
+            .collect();
+        let cycles = Cycles::new(&bots);
+        let period = cycles.period().expect("Period");
+        let first_repeat = cycles.first_repeat().expect("First repeat");
+
+        assert_eq!(period, 12);
+        assert_eq!(cycles.transient(), 2);
+        assert_eq!(first_repeat, 14);
+        for turn in cycles.transient()..first_repeat + period {
+            assert_eq!(
+                cycles.occupied_cells(turn),
+                cycles.occupied_cells(turn + period),
+                "turn {turn}"
+            );
+        }
+        // The parking bot has not parked yet one turn earlier
+        assert_ne!(
+            cycles.bots[2].cell(cycles.transient() - 1),
+            cycles.bots[2].cell(first_repeat - 1)
+        );
+    }
+
+    #[test]
+    fn busyness_is_the_share_of_time_on_each_cell() {
+        let line = vec![IVec2::new(0, 0), IVec2::new(1, 0), IVec2::new(2, 0)];
+        let busyness =
+            |behavior, path: Vec<IVec2>| Cycles::new(&[bot(path, behavior, 0)]).busyness();
+
+        assert!(busyness(BotBehavior::Loop, square())
+            .values()
+            .all(|&busyness| busyness == 0.25));
+        assert_eq!(
+            busyness(BotBehavior::PingPong, line.clone()),
+            HashMap::from([(line[0], 0.25), (line[1], 0.5), (line[2], 0.25)])
+        );
+        assert_eq!(
+            busyness(BotBehavior::Once, line.clone()),
+            HashMap::from([(line[0], 1.0)])
+        );
+        assert!(busyness(BotBehavior::EveryNth(3), square())
+            .values()
+            .all(|&busyness| busyness == 0.25));
+
+        let bots: Vec<Character> = BEHAVIORS
+            .into_iter()
+            .map(|behavior| bot(square(), behavior, 0))
+            .collect();
+        let total: f32 = Cycles::new(&bots).busyness().values().sum();
+        assert!((total - bots.len() as f32).abs() < 1e-5);
+    }
+}
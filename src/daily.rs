@@ -0,0 +1,247 @@
+//! Daily challenge: the same board for everyone on a given date, with one
+//! scored run per day kept in a local history.
+
+use crate::game_state::{Game, GameState};
+use crate::grid::GridConfig;
+use crate::levels::Level;
+use crate::modes::GameMode;
+use crate::rng::GameRng;
+use crate::simulation::Simulation;
+use crate::storage::VersionedFile;
+use bevy::color::palettes::css::YELLOW;
+use bevy::prelude::*;
+use chrono::{Datelike, NaiveDate};
+use std::path::PathBuf;
+
+const DAILY_FILE: VersionedFile = VersionedFile {
+    name: "daily results",
+    file_name: "daily.txt",
+    versions: 1..=1,
+};
+
+/// Mode of the board of the day, whatever the chosen one.
+pub const DAILY_MODE: GameMode = GameMode::Classic;
+
+/// Days shown on the end screen, the most recent first.
+const SHOWN_DAYS: usize = 10;
+
+/// Seed of the board of `date`, like `20240721`, the same on every computer.
+pub fn daily_seed(date: NaiveDate) -> u64 {
+    date.year() as u64 * 10000 + date.month() as u64 * 100 + date.day() as u64
+}
+
+/// The scored run of one day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DailyResult {
+    pub date: NaiveDate,
+    pub journeys: u32,
+    pub attempts_used: u32,
+    pub bonus: u32,
+}
+
+impl DailyResult {
+    fn encode(&self) -> String {
+        format!(
+            "{} {} {} {}",
+            self.date, self.journeys, self.attempts_used, self.bonus
+        )
+    }
+
+    fn decode(line: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid daily result '{line}'");
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [date, journeys, attempts_used, bonus] = fields[..] else {
+            return Err(invalid());
+        };
+        Ok(Self {
+            date: date.parse().map_err(|_| invalid())?,
+            journeys: journeys.parse().map_err(|_| invalid())?,
+            attempts_used: attempts_used.parse().map_err(|_| invalid())?,
+            bonus: bonus.parse().map_err(|_| invalid())?,
+        })
+    }
+
+    fn text(&self) -> String {
+        let bonus = if self.bonus > 0 {
+            format!(" +{}", self.bonus)
+        } else {
+            String::new()
+        };
+        format!(
+            "{}: {} journeys{bonus}, {} attempts used\n",
+            self.date, self.journeys, self.attempts_used
+        )
+    }
+}
+
+/// Every daily challenge played, by date, and where they are saved.
+///
+/// Stored as a small text file, one day per line, the most recent first:
+/// ```text
+/// road-on-road daily results 1
+/// 2024-07-21 12 7 0
+/// ```
+#[derive(Debug, Resource)]
+pub struct DailyResults {
+    pub results: Vec<DailyResult>,
+    path: Option<PathBuf>,
+}
+
+impl DailyResults {
+    pub fn encode(&self) -> String {
+        let mut text = String::new();
+        for result in self.results.iter() {
+            text += &result.encode();
+            text += "\n";
+        }
+        DAILY_FILE.encode(&text)
+    }
+
+    pub fn decode(text: &str) -> Result<Vec<DailyResult>, String> {
+        let (_, lines) = DAILY_FILE.decode(text)?;
+        lines
+            .filter(|line| !line.trim().is_empty())
+            .map(DailyResult::decode)
+            .collect()
+    }
+
+    /// Loads the history from the data directory, empty if there is none yet.
+    pub fn load() -> Self {
+        let (path, results) = DAILY_FILE.load(Self::decode);
+        let results = results.unwrap_or_default();
+        Self { results, path }
+    }
+
+    fn save(&self) {
+        DAILY_FILE.save(self.path.as_deref(), &self.encode());
+    }
+
+    pub fn get(&self, date: NaiveDate) -> Option<&DailyResult> {
+        self.results.iter().find(|result| result.date == date)
+    }
+
+    /// Replaces the result of its date, keeping the most recent first.
+    fn set(&mut self, result: DailyResult) {
+        self.results.retain(|other| other.date != result.date);
+        let index = self
+            .results
+            .iter()
+            .position(|other| other.date < result.date)
+            .unwrap_or(self.results.len());
+        self.results.insert(index, result);
+    }
+}
+
+impl FromWorld for DailyResults {
+    fn from_world(_world: &mut World) -> Self {
+        Self::load()
+    }
+}
+
+/// The daily challenge being played, and the board size to go back to.
+#[derive(Debug, Resource)]
+pub struct DailyChallenge {
+    pub date: NaiveDate,
+    /// The current run is the scored one of the day.
+    scored: bool,
+    previous_grid: GridConfig,
+}
+
+impl DailyChallenge {
+    /// Challenge of today, played on the default board size.
+    pub fn today(grid: &mut GridConfig) -> Self {
+        let previous_grid = *grid;
+        *grid = GridConfig::default();
+        Self {
+            date: chrono::Local::now().date_naive(),
+            scored: false,
+            previous_grid,
+        }
+    }
+}
+
+/// Builds the board of the day whatever the settings, mode and level, and
+/// starts its scored run if the day has none yet.
+///
+/// A run counts as soon as it starts, restarting or leaving ends it.
+pub fn start_daily_game(
+    mut challenge: ResMut<DailyChallenge>,
+    mut results: ResMut<DailyResults>,
+    mut game: ResMut<Game>,
+    mut rng: ResMut<GameRng>,
+    grid: Res<GridConfig>,
+) {
+    let mut simulation = Simulation::new(&Level::empty(grid.size));
+    simulation.bot_behaviors = DAILY_MODE.bot_behaviors();
+    *game = Game(simulation);
+    *rng = GameRng::from_seed(daily_seed(challenge.date));
+
+    challenge.scored = results.get(challenge.date).is_none();
+    if challenge.scored {
+        results.set(DailyResult {
+            date: challenge.date,
+            journeys: 0,
+            attempts_used: 0,
+            bonus: 0,
+        });
+        results.save();
+    }
+}
+
+/// Keeps the score of the scored run once it ends, on the end screen or when
+/// it is left before.
+pub fn finish_daily_run(
+    mut challenge: ResMut<DailyChallenge>,
+    mut results: ResMut<DailyResults>,
+    game: Res<Game>,
+) {
+    if !challenge.scored {
+        return;
+    }
+    challenge.scored = false;
+    results.set(DailyResult {
+        date: challenge.date,
+        journeys: game.score.nb_journeys,
+        attempts_used: game.score.attempts_used,
+        bonus: game.score.bonus,
+    });
+    results.save();
+}
+
+/// Puts back the board size played before the challenge.
+pub fn leave_daily_challenge(
+    mut commands: Commands,
+    challenge: Res<DailyChallenge>,
+    mut grid: ResMut<GridConfig>,
+) {
+    *grid = challenge.previous_grid;
+    commands.remove_resource::<DailyChallenge>();
+}
+
+/// Latest days played, in place of the high scores on the end screen.
+pub fn spawn_daily_results_text(
+    mut commands: Commands,
+    results: Res<DailyResults>,
+    challenge: Res<DailyChallenge>,
+) {
+    let style = TextStyle {
+        font_size: 16.0,
+        ..default()
+    };
+    let mut sections = vec![TextSection::new("Daily challenges\n", style.clone())];
+    for result in results.results.iter().take(SHOWN_DAYS) {
+        let mut style = style.clone();
+        if result.date == challenge.date {
+            style.color = YELLOW.into();
+        }
+        sections.push(TextSection::new(result.text(), style));
+    }
+
+    commands.spawn((
+        StateScoped(GameState::EndGame),
+        Text2dBundle {
+            text: Text::from_sections(sections).with_justify(JustifyText::Left),
+            ..default()
+        },
+    ));
+}
@@ -0,0 +1,56 @@
+//! Overlay marking where the bots go on the next turns, toggled with O.
+
+use crate::game_state::GameCycles;
+use crate::grid::GridConfig;
+use bevy::color::palettes::css::ORANGE;
+use bevy::prelude::*;
+
+/// Turns ahead shown, the furthest ones fainter.
+const DANGER_TURNS: usize = 3;
+
+#[derive(Debug, Resource, Default)]
+pub struct DangerOverlay {
+    pub enabled: bool,
+}
+
+pub fn toggle_danger_overlay(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut overlay: ResMut<DangerOverlay>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyO) {
+        overlay.enabled = !overlay.enabled;
+    }
+}
+
+pub fn danger_overlay_enabled(overlay: Res<DangerOverlay>) -> bool {
+    overlay.enabled
+}
+
+pub fn draw_danger_overlay(
+    mut gizmos: Gizmos,
+    overlay: Res<DangerOverlay>,
+    game_cycles: Res<GameCycles>,
+    grid: Res<GridConfig>,
+) {
+    let Some(cycles) = game_cycles.get().filter(|_| overlay.enabled) else {
+        return;
+    };
+    let cell_size = grid.cell_size();
+
+    // Furthest turns first, so that the nearest danger of a cell is on top
+    for turn in (1..=DANGER_TURNS).rev() {
+        let alpha = 1. - (turn - 1) as f32 / DANGER_TURNS as f32;
+        for cell in cycles.occupied_cells(turn) {
+            let center = grid.sprite_position_translation(cell.as_vec2());
+            let corner = Vec2::splat(cell_size * 0.3);
+            let color = ORANGE.with_alpha(alpha);
+
+            gizmos.line_2d(center - corner, center + corner, color);
+            gizmos.line_2d(
+                center + corner.with_x(-corner.x),
+                center - corner.with_x(-corner.x),
+                color,
+            );
+        }
+    }
+}
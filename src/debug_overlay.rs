@@ -0,0 +1,104 @@
+//! Overlay showing how the bots cycle, toggled with F3.
+
+use crate::cycles::Cycles;
+use crate::game_state::GameCycles;
+use crate::grid::GridConfig;
+use crate::routes::MAX_SEARCH_TURNS;
+use bevy::color::palettes::css::RED;
+use bevy::prelude::*;
+
+#[derive(Debug, Resource, Default)]
+pub struct DebugOverlay {
+    pub enabled: bool,
+}
+
+pub fn toggle_debug_overlay(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut overlay: ResMut<DebugOverlay>,
+) {
+    if keyboard.just_pressed(KeyCode::F3) {
+        overlay.enabled = !overlay.enabled;
+    }
+}
+
+pub fn debug_overlay_enabled(overlay: Res<DebugOverlay>) -> bool {
+    overlay.enabled
+}
+
+/// Shades each cell by the average number of bots on it, and marks the cells
+/// taken by a bot on the next turn.
+pub fn draw_debug_overlay(
+    mut gizmos: Gizmos,
+    overlay: Res<DebugOverlay>,
+    game_cycles: Res<GameCycles>,
+    grid: Res<GridConfig>,
+) {
+    let Some(cycles) = game_cycles.get().filter(|_| overlay.enabled) else {
+        return;
+    };
+    let cell_size = grid.cell_size();
+
+    for (cell, busyness) in cycles.busyness() {
+        let center = grid.sprite_position_translation(cell.as_vec2());
+        gizmos.rect_2d(
+            center,
+            0.,
+            Vec2::splat(cell_size * 0.9),
+            RED.with_alpha(busyness.min(1.)),
+        );
+        if cycles.is_occupied(cell, 1) {
+            gizmos.circle_2d(center, cell_size * 0.1, RED);
+        }
+    }
+}
+
+fn cycle_text(cycles: &Cycles) -> String {
+    let (Some(period), Some(repeat)) = (cycles.period(), cycles.first_repeat()) else {
+        return "Board never repeats".to_string();
+    };
+    let mut text = format!("Board repeats every {period} turns");
+    if cycles.transient() > 0 {
+        text += &format!(", first in {repeat}");
+    }
+    if repeat > MAX_SEARCH_TURNS {
+        text += &format!("\nRoutes only searched up to {MAX_SEARCH_TURNS} turns");
+    }
+    text
+}
+
+#[derive(Debug, Component)]
+pub struct DebugOverlayDisplay;
+
+pub fn spawn_debug_overlay_display(mut commands: Commands, grid: Res<GridConfig>) {
+    let text_position = Vec3::new(0., -(grid.window_size / 2. - 50.), 0.);
+
+    commands.spawn((
+        DebugOverlayDisplay,
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 14.0,
+                    color: RED.into(),
+                    ..default()
+                },
+            )
+            .with_justify(JustifyText::Center),
+            transform: Transform::from_translation(text_position),
+            ..default()
+        },
+    ));
+}
+
+pub fn update_debug_overlay_display(
+    mut displays: Query<&mut Text, With<DebugOverlayDisplay>>,
+    overlay: Res<DebugOverlay>,
+    game_cycles: Res<GameCycles>,
+) {
+    for mut text in displays.iter_mut() {
+        text.sections[0].value = match game_cycles.get().filter(|_| overlay.enabled) {
+            Some(cycles) => cycle_text(cycles),
+            None => String::new(),
+        };
+    }
+}
@@ -1,50 +1,138 @@
 use crate::components::*;
-use crate::movements::position_translation;
+use crate::game_state::Game;
+use crate::grid::GridConfig;
+use crate::simulation::{BotBehavior, WAIT};
 use bevy::prelude::*;
 
-use crate::CELL_SIZE;
-use crate::GRID_SIZE;
+pub fn draw_grid(mut gizmos: Gizmos, game: Res<Game>, grid: Res<GridConfig>) {
+    let cell_size = grid.cell_size();
 
-pub fn draw_grid(mut gizmos: Gizmos) {
     gizmos
         .grid_2d(
             Vec2::ZERO,
             0.0,
-            GRID_SIZE,
-            Vec2::new(CELL_SIZE, CELL_SIZE),
+            grid.size,
+            Vec2::new(cell_size, cell_size),
             LinearRgba::gray(0.05),
         )
         .outer_edges();
+
+    for wall in game.walls.iter() {
+        let center = grid.position_translation(&Position(*wall)) + cell_size / 2.;
+        let corner = Vec2::splat(cell_size * 0.4);
+        let color = LinearRgba::gray(0.3);
+
+        gizmos.rect_2d(center, 0., corner * 2., color);
+        gizmos.line_2d(center - corner, center + corner, color);
+        gizmos.line_2d(
+            center + corner.with_x(-corner.x),
+            center - corner.with_x(-corner.x),
+            color,
+        );
+    }
 }
 
 pub fn draw_targets(
     mut gizmos: Gizmos,
     journeys: Query<&Journey, Or<(With<Player>, With<GameFinishedPlayer>)>>,
+    grid: Res<GridConfig>,
 ) {
+    let cell_size = grid.cell_size();
+
     for journey in journeys.iter() {
         gizmos.rounded_rect_2d(
-            position_translation(&journey.start_pos) + CELL_SIZE / 2.,
+            grid.position_translation(&journey.start_pos) + cell_size / 2.,
             0.,
-            Vec2::splat(CELL_SIZE),
+            Vec2::splat(cell_size),
             journey.color,
         );
         gizmos.circle_2d(
-            position_translation(&journey.target_pos) + CELL_SIZE / 2.,
-            CELL_SIZE / 2. * 1.1,
+            grid.position_translation(&journey.target_pos) + cell_size / 2.,
+            cell_size / 2. * 1.1,
             journey.color,
         );
     }
 }
 
-pub fn draw_paths(mut gizmos: Gizmos, journeys: Query<&Journey, WithPlayerOrAutomated>) {
+pub fn draw_queued_moves(
+    mut gizmos: Gizmos,
+    players: Query<(&Journey, &Position, Option<&Transition>, &InputQueue), With<Player>>,
+    game: Res<Game>,
+    grid: Res<GridConfig>,
+) {
+    for (journey, pos, transition, queue) in players.iter() {
+        let mut current = transition.map_or(pos.0, |transition| transition.end.0);
+
+        for direction in queue.0.iter() {
+            if *direction == WAIT {
+                gizmos.circle_2d(
+                    grid.sprite_position_translation(current.as_vec2()),
+                    grid.cell_size() * 0.2,
+                    journey.color.with_alpha(0.5),
+                );
+                continue;
+            }
+            let next = grid.clamp(current + *direction);
+            if game.walls.contains(&next) {
+                break;
+            }
+            gizmos.arrow_2d(
+                grid.sprite_position_translation(current.as_vec2()),
+                grid.sprite_position_translation(next.as_vec2()),
+                journey.color.with_alpha(0.5),
+            );
+            current = next;
+        }
+    }
+}
+
+pub fn draw_paths(
+    mut gizmos: Gizmos,
+    journeys: Query<&Journey, WithPlayerOrAutomated>,
+    grid: Res<GridConfig>,
+) {
+    let cell_size = grid.cell_size();
+
     for journey in journeys.iter() {
         for pos in journey.path.iter() {
             gizmos.rect_2d(
-                position_translation(pos) + CELL_SIZE / 2.,
+                grid.position_translation(pos) + cell_size / 2.,
                 0.,
-                Vec2::splat(CELL_SIZE * journey.scale),
+                Vec2::splat(cell_size * journey.scale),
                 journey.color.with_alpha(0.05),
             );
         }
     }
 }
+
+/// Marks the bots that do not simply loop, in the corner of their cell.
+pub fn draw_bot_behaviors(
+    mut gizmos: Gizmos,
+    bots: Query<(&Journey, &Transform), With<Automated>>,
+    grid: Res<GridConfig>,
+) {
+    let cell_size = grid.cell_size();
+
+    for (journey, transform) in bots.iter() {
+        let corner = transform.translation.truncate() + Vec2::splat(cell_size * 0.35);
+        let size = cell_size * 0.12;
+        let color = journey.color;
+
+        match journey.behavior {
+            BotBehavior::Loop => {}
+            BotBehavior::PingPong => {
+                gizmos.arrow_2d(corner, corner + Vec2::X * size, color);
+                gizmos.arrow_2d(corner, corner - Vec2::X * size, color);
+            }
+            BotBehavior::Once => {
+                gizmos.rect_2d(corner, 0., Vec2::splat(size * 1.5), color);
+            }
+            BotBehavior::EveryNth(n) => {
+                for i in 0..n {
+                    let offset = (i as f32 - (n - 1) as f32 / 2.) * size;
+                    gizmos.circle_2d(corner + Vec2::X * offset, size * 0.3, color);
+                }
+            }
+        }
+    }
+}
@@ -1,38 +1,206 @@
 use bevy::prelude::*;
 
-use crate::{characters, components::GameObject, scores, CELL_SIZE, WINDOW_SIZE};
+use crate::{
+    characters,
+    components::GameObject,
+    cycles::Cycles,
+    grid::GridConfig,
+    levels::Level,
+    modes::GameMode,
+    puzzles::{PuzzleResults, PuzzleSession},
+    rng::GameRng,
+    settings::{rules_name, Settings},
+    simulation::{Rules, Simulation, VICTORY_BONUS},
+};
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
 pub enum GameState {
     #[default]
+    Title,
+    HighScores,
+    Settings,
+    Puzzles,
     InGame,
     EndGame,
+    /// The game was left from the pause menu, the next one starts on the
+    /// next frame.
+    Restarting,
 }
 
+/// Whether the game in progress is frozen behind the pause menu.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, SubStates)]
+#[source(GameState = GameState::InGame)]
+pub enum PauseState {
+    #[default]
+    Running,
+    Paused,
+}
+
+/// Starts the next game once the one left is cleared up.
+pub fn finish_restart(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::InGame);
+}
+
+/// The simulation of the current game, played back by the systems.
+#[derive(Debug, Resource, Deref, DerefMut)]
+pub struct Game(pub Simulation);
+
+impl Game {
+    pub fn new(
+        level: &CurrentLevel,
+        grid: &GridConfig,
+        mode: GameMode,
+        settings: &Settings,
+    ) -> Self {
+        let mut simulation = match &level.0 {
+            Some(level) => Simulation::new(level),
+            None => Simulation::new(&Level::empty(grid.size)),
+        };
+        simulation.rules = settings.rules;
+        simulation.attempts = settings.attempts;
+        simulation.score.remaining_attempts = settings.attempts;
+        // Puzzles are played the same way whatever the settings, hits only
+        // cost moves
+        if simulation.fixed_only {
+            simulation.rules = Rules::Unlimited;
+        } else {
+            simulation.bot_behaviors = mode.bot_behaviors();
+        }
+        Self(simulation)
+    }
+}
+
+impl FromWorld for Game {
+    fn from_world(world: &mut World) -> Self {
+        Self::new(
+            world.resource::<CurrentLevel>(),
+            world.resource::<GridConfig>(),
+            *world.resource::<GameMode>(),
+            world.resource::<Settings>(),
+        )
+    }
+}
+
+/// Cycles of the bots of the [`Game`] for the overlays, computed once per
+/// turn rather than on every frame.
+#[derive(Debug, Resource, Default)]
+pub struct GameCycles(Option<(u32, Cycles)>);
+
+impl GameCycles {
+    pub fn get(&self) -> Option<&Cycles> {
+        self.0.as_ref().map(|(_, cycles)| cycles)
+    }
+}
+
+/// Computes the cycles again once a turn is played, only while an overlay
+/// shows them.
+pub fn update_game_cycles(game: Res<Game>, mut game_cycles: ResMut<GameCycles>) {
+    if game_cycles.0.as_ref().map(|(turn, _)| *turn) != Some(game.turn) {
+        game_cycles.0 = Some((game.turn, Cycles::new(&game.bots)));
+    }
+}
+
+/// A new game starts over from turn 0 with other bots.
+pub fn forget_game_cycles(mut game_cycles: ResMut<GameCycles>) {
+    game_cycles.0 = None;
+}
+
+/// The handcrafted board every new game starts from, random boards otherwise.
+#[derive(Debug, Resource, Default)]
+pub struct CurrentLevel(pub Option<Level>);
+
 pub fn clear_up_game_entities(
     mut commands: Commands,
     game_entities: Query<Entity, With<GameObject>>,
     mut characters: ResMut<characters::Characters>,
-    mut scores: ResMut<scores::Score>,
+    mut game: ResMut<Game>,
+    level: Res<CurrentLevel>,
+    grid: Res<GridConfig>,
+    mode: Res<GameMode>,
+    settings: Res<Settings>,
+    mut rng: ResMut<GameRng>,
 ) {
     for entity in game_entities.iter() {
         commands.entity(entity).despawn();
     }
     *characters = characters::Characters::default();
-    *scores = scores::Score::default();
+    *game = Game::new(&level, &grid, *mode, &settings);
+    rng.next_game();
+}
+
+/// Replays start right away, without the title screen.
+pub fn skip_title(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::InGame);
 }
 
-pub fn spawn_restart_text(mut commands: Commands) {
-    commands.spawn((StateScoped(GameState::EndGame), lose_text()));
-    commands.spawn((StateScoped(GameState::EndGame), restart_text()));
+/// Builds the board of the next game again, the mode may have changed since.
+pub fn new_game(
+    mut game: ResMut<Game>,
+    level: Res<CurrentLevel>,
+    grid: Res<GridConfig>,
+    mode: Res<GameMode>,
+    settings: Res<Settings>,
+) {
+    *game = Game::new(&level, &grid, *mode, &settings);
+}
+
+/// Title and reason of the end of `game`, which may also have been stopped
+/// before its end, like the demo.
+fn ending(game: &Simulation) -> (&'static str, String) {
+    if game.victory {
+        (
+            "Victory!",
+            format!("The board is full: +{VICTORY_BONUS} points"),
+        )
+    } else if game.game_over && game.player.is_none() {
+        // Other ends keep the player that was playing
+        ("Game ended", "No safe journey left".to_string())
+    } else if game.game_over && game.rules == Rules::Lives {
+        ("Game ended", "Out of lives".to_string())
+    } else if game.game_over && game.rules == Rules::Arcade {
+        ("Time up!", "Out of time".to_string())
+    } else if game.game_over {
+        ("Game ended", "Out of attempts".to_string())
+    } else {
+        ("Game ended", "Game stopped".to_string())
+    }
+}
+
+pub fn spawn_restart_text(
+    mut commands: Commands,
+    game: Res<Game>,
+    rng: Res<GameRng>,
+    grid: Res<GridConfig>,
+    puzzle: Option<Res<PuzzleSession>>,
+    puzzle_results: Res<PuzzleResults>,
+) {
+    let (title, text) = match puzzle {
+        Some(puzzle) => {
+            let (title, reason) = puzzle.ending(&game, &puzzle_results);
+            let text = format!("{reason}\nPress space to restart, escape for the puzzles");
+            (title, text)
+        }
+        None => {
+            let (title, reason) = ending(&game);
+            let text = format!(
+                "{reason}\nRules: {}, seed: {}\n\
+                Press space to restart, escape for the title screen",
+                rules_name(game.rules),
+                rng.seed
+            );
+            (title, text)
+        }
+    };
+    commands.spawn((StateScoped(GameState::EndGame), ending_text(&grid, title)));
+    commands.spawn((StateScoped(GameState::EndGame), restart_text(&grid, text)));
 }
 
-fn lose_text() -> Text2dBundle {
-    let text_position = Vec3::new(0., WINDOW_SIZE / 2. - CELL_SIZE - 32., 0.);
+fn ending_text(grid: &GridConfig, title: &str) -> Text2dBundle {
+    let text_position = Vec3::new(0., grid.window_size / 2. - grid.cell_size() - 32., 0.);
 
     Text2dBundle {
         text: Text::from_section(
-            "Game ended",
+            title,
             TextStyle {
                 font_size: 48.0,
                 ..default()
@@ -44,12 +212,12 @@ fn lose_text() -> Text2dBundle {
     }
 }
 
-fn restart_text() -> Text2dBundle {
-    let text_position = Vec3::new(0., -(WINDOW_SIZE / 2. - CELL_SIZE), 0.);
+fn restart_text(grid: &GridConfig, text: String) -> Text2dBundle {
+    let text_position = Vec3::new(0., -(grid.window_size / 2. - grid.cell_size()), 0.);
 
     Text2dBundle {
         text: Text::from_section(
-            "Press space to restart",
+            text,
             TextStyle {
                 font_size: 20.0,
                 ..default()
@@ -61,12 +229,18 @@ fn restart_text() -> Text2dBundle {
     }
 }
 
+/// Escape goes back to the puzzle list after a puzzle.
 pub fn handle_restart_input(
     keyboard: Res<ButtonInput<KeyCode>>,
     touches: Res<Touches>,
+    puzzle: Option<Res<PuzzleSession>>,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
     if keyboard.just_pressed(KeyCode::Space) || touches.any_just_released() {
         next_state.set(GameState::InGame);
+    } else if keyboard.just_pressed(KeyCode::Escape) && puzzle.is_some() {
+        next_state.set(GameState::Puzzles);
+    } else if keyboard.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Title);
     }
 }
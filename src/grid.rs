@@ -0,0 +1,72 @@
+use crate::components::Position;
+use bevy::prelude::*;
+
+pub const WINDOW_SIZE: f32 = 600.;
+
+pub const MIN_GRID_SIZE: u32 = 4;
+pub const MAX_GRID_SIZE: u32 = 20;
+
+/// Cells left around the grid for the texts, split between both sides.
+const MARGIN_CELLS: u32 = 4;
+
+/// Size of the board and its layout on screen, cells shrink as the grid grows.
+#[derive(Debug, Resource, Clone, Copy, PartialEq)]
+pub struct GridConfig {
+    pub size: UVec2,
+    /// Side of the square area framed by the camera.
+    pub window_size: f32,
+}
+
+impl Default for GridConfig {
+    fn default() -> Self {
+        Self {
+            size: UVec2::new(6, 6),
+            window_size: WINDOW_SIZE,
+        }
+    }
+}
+
+impl GridConfig {
+    pub fn new(size: UVec2) -> Result<Self, String> {
+        if size.min_element() < MIN_GRID_SIZE || size.max_element() > MAX_GRID_SIZE {
+            return Err(format!(
+                "Grid size must be between {MIN_GRID_SIZE} and {MAX_GRID_SIZE}, got {}x{}",
+                size.x, size.y
+            ));
+        }
+        Ok(Self { size, ..default() })
+    }
+
+    /// Parses a size written like `8x6`.
+    pub fn parse(size: &str) -> Result<Self, String> {
+        let (x, y) = size
+            .split_once('x')
+            .ok_or(format!("Invalid grid size '{size}'"))?;
+        let parse = |n: &str| {
+            n.trim()
+                .parse()
+                .map_err(|_| format!("Invalid grid size '{size}'"))
+        };
+        Self::new(UVec2::new(parse(x)?, parse(y)?))
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.window_size / (self.size.max_element() + MARGIN_CELLS) as f32
+    }
+
+    pub fn clamp(&self, pos: IVec2) -> IVec2 {
+        pos.clamp(IVec2::ZERO, self.size.as_ivec2() - 1)
+    }
+
+    pub fn position_translation(&self, pos: &Position) -> Vec2 {
+        self.grid_pos_translation(pos.0.as_vec2())
+    }
+
+    pub fn sprite_position_translation(&self, pos: Vec2) -> Vec2 {
+        self.grid_pos_translation(pos) + self.cell_size() / 2.
+    }
+
+    pub fn grid_pos_translation(&self, pos: Vec2) -> Vec2 {
+        pos * self.cell_size() - self.size.as_vec2() * self.cell_size() / 2.
+    }
+}
@@ -0,0 +1,278 @@
+//! Best games of each mode, kept between sessions in the data directory.
+
+use crate::autopilot::Autopilot;
+use crate::game_state::{Game, GameState};
+use crate::grid::GridConfig;
+use crate::modes::GameMode;
+use crate::rng::GameRng;
+use crate::settings::{rules_name, Settings};
+use crate::simulation::Rules;
+use crate::storage::VersionedFile;
+use bevy::color::palettes::css::YELLOW;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use chrono::NaiveDate;
+use std::path::PathBuf;
+
+/// Bumped when the format of the entries changes, older ones are still read.
+const HIGH_SCORES_FILE: VersionedFile = VersionedFile {
+    name: "high scores",
+    file_name: "high_scores.txt",
+    versions: 1..=3,
+};
+
+/// Entries kept for each mode and rules.
+const MAX_ENTRIES: usize = 10;
+
+/// One finished game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighScore {
+    pub journeys: u32,
+    pub attempts_used: u32,
+    pub seed: u64,
+    pub grid_size: UVec2,
+    pub mode: GameMode,
+    pub date: NaiveDate,
+    /// Points of a victory, added to the journeys.
+    pub bonus: u32,
+    pub rules: Rules,
+}
+
+impl HighScore {
+    fn encode(&self) -> String {
+        format!(
+            "{} {} {} {}x{} {} {} {} {}",
+            self.journeys,
+            self.attempts_used,
+            self.seed,
+            self.grid_size.x,
+            self.grid_size.y,
+            self.mode.name(),
+            self.date,
+            self.bonus,
+            self.rules.name()
+        )
+    }
+
+    /// Reads an entry written by the given `version` of the format, the first
+    /// one has no bonus and the first two were all played with attempts per
+    /// journey.
+    fn decode(line: &str, version: u32) -> Result<Self, String> {
+        let invalid = || format!("Invalid high score '{line}'");
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (fields, bonus, rules) = match (version, &fields[..]) {
+            (1, fields) => (fields, "0", Rules::AttemptsPerJourney),
+            (2, [fields @ .., bonus]) => (fields, *bonus, Rules::AttemptsPerJourney),
+            (_, [fields @ .., bonus, rules]) => (fields, *bonus, Rules::parse(rules)?),
+            _ => return Err(invalid()),
+        };
+        let [journeys, attempts_used, seed, grid_size, mode, date] = fields[..] else {
+            return Err(invalid());
+        };
+        Ok(Self {
+            journeys: journeys.parse().map_err(|_| invalid())?,
+            attempts_used: attempts_used.parse().map_err(|_| invalid())?,
+            seed: seed.parse().map_err(|_| invalid())?,
+            grid_size: GridConfig::parse(grid_size)?.size,
+            mode: GameMode::parse(mode)?,
+            date: date.parse().map_err(|_| invalid())?,
+            bonus: bonus.parse().map_err(|_| invalid())?,
+            rules,
+        })
+    }
+
+    fn points(&self) -> u32 {
+        self.journeys + self.bonus
+    }
+
+    /// More points first, then fewer attempts.
+    fn is_better_than(&self, other: &Self) -> bool {
+        (self.points(), other.attempts_used) > (other.points(), self.attempts_used)
+    }
+
+    fn text(&self, rank: usize) -> String {
+        let bonus = if self.bonus > 0 {
+            format!(" +{}", self.bonus)
+        } else {
+            String::new()
+        };
+        format!(
+            "{rank:>2}. {} journeys{bonus}, {} attempts used, {}x{}, {}\n",
+            self.journeys, self.attempts_used, self.grid_size.x, self.grid_size.y, self.date
+        )
+    }
+}
+
+/// The high-score table and where it is saved, nowhere on the web.
+///
+/// Stored as a small text file, one entry per line, best first:
+/// ```text
+/// road-on-road high scores 3
+/// 12 7 1234 6x6 classic 2024-07-21 0 attempts
+/// ```
+#[derive(Debug, Resource)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+    path: Option<PathBuf>,
+    /// Index of the entry of the game that just ended, if it made the table.
+    new_entry: Option<usize>,
+}
+
+impl HighScores {
+    pub fn encode(&self) -> String {
+        let mut text = String::new();
+        for entry in self.entries.iter() {
+            text += &entry.encode();
+            text += "\n";
+        }
+        HIGH_SCORES_FILE.encode(&text)
+    }
+
+    pub fn decode(text: &str) -> Result<Vec<HighScore>, String> {
+        let (version, lines) = HIGH_SCORES_FILE.decode(text)?;
+        lines
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| HighScore::decode(line, version))
+            .collect()
+    }
+
+    /// Loads the table from the data directory, empty if there is none yet or
+    /// it is unreadable, kept aside then.
+    pub fn load() -> Self {
+        let (path, entries) = HIGH_SCORES_FILE.load(Self::decode);
+        let entries = entries.unwrap_or_default();
+        Self {
+            entries,
+            path,
+            new_entry: None,
+        }
+    }
+
+    fn save(&self) {
+        HIGH_SCORES_FILE.save(self.path.as_deref(), &self.encode());
+    }
+
+    /// Adds `entry` to the table, returning its index if it is among the best
+    /// of its mode and rules.
+    pub fn insert(&mut self, entry: HighScore) -> Option<usize> {
+        let index = self
+            .entries
+            .iter()
+            .position(|other| entry.is_better_than(other))
+            .unwrap_or(self.entries.len());
+        let category = (entry.mode, entry.rules);
+        self.entries.insert(index, entry);
+
+        let rank = self.entries[..index]
+            .iter()
+            .filter(|other| (other.mode, other.rules) == category)
+            .count();
+        let overflow = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, other)| (other.mode, other.rules) == category)
+            .nth(MAX_ENTRIES);
+        if let Some((overflow, _)) = overflow {
+            self.entries.remove(overflow);
+        }
+        (rank < MAX_ENTRIES).then_some(index)
+    }
+
+    /// Entries of `mode` and `rules`, best first, with their index in the
+    /// table.
+    pub fn of_category(
+        &self,
+        mode: GameMode,
+        rules: Rules,
+    ) -> impl Iterator<Item = (usize, &HighScore)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(move |(_, entry)| entry.mode == mode && entry.rules == rules)
+    }
+}
+
+impl FromWorld for HighScores {
+    fn from_world(_world: &mut World) -> Self {
+        Self::load()
+    }
+}
+
+/// Adds the game that just ended to the table, unless the autopilot played it
+/// or it was only practice.
+pub fn record_high_score(
+    mut high_scores: ResMut<HighScores>,
+    game: Res<Game>,
+    rng: Res<GameRng>,
+    grid: Res<GridConfig>,
+    mode: Res<GameMode>,
+    autopilot: Res<Autopilot>,
+) {
+    if autopilot.enabled || game.rules == Rules::Unlimited {
+        return;
+    }
+    high_scores.new_entry = high_scores.insert(HighScore {
+        journeys: game.score.nb_journeys,
+        attempts_used: game.score.attempts_used,
+        seed: rng.seed,
+        grid_size: grid.size,
+        mode: *mode,
+        date: chrono::Local::now().date_naive(),
+        bonus: game.score.bonus,
+        rules: game.rules,
+    });
+    high_scores.save();
+}
+
+/// Only the end screen following the game highlights its entry.
+pub fn clear_new_high_score(mut high_scores: ResMut<HighScores>) {
+    high_scores.new_entry = None;
+}
+
+/// Table of the current mode and rules, on the end screen or the high-score
+/// screen. The end screen shows the rules of the game just played, under its
+/// title.
+pub fn spawn_high_scores_text(
+    mut commands: Commands,
+    high_scores: Res<HighScores>,
+    mode: Res<GameMode>,
+    settings: Res<Settings>,
+    game: Res<Game>,
+    grid: Res<GridConfig>,
+    state: Res<State<GameState>>,
+) {
+    let (rules, text_anchor, text_position) = match state.get() {
+        GameState::EndGame => (
+            game.rules,
+            Anchor::TopCenter,
+            Vec3::new(0., grid.window_size / 2. - grid.cell_size() - 64., 0.),
+        ),
+        _ => (settings.rules, Anchor::Center, Vec3::ZERO),
+    };
+    let style = TextStyle {
+        font_size: 16.0,
+        ..default()
+    };
+    let mut sections = vec![TextSection::new(
+        format!("High scores ({}, {})\n", mode.name(), rules_name(rules)),
+        style.clone(),
+    )];
+    for (rank, (index, entry)) in high_scores.of_category(*mode, rules).enumerate() {
+        let mut style = style.clone();
+        if high_scores.new_entry == Some(index) {
+            style.color = YELLOW.into();
+        }
+        sections.push(TextSection::new(entry.text(rank + 1), style));
+    }
+
+    commands.spawn((
+        StateScoped(*state.get()),
+        Text2dBundle {
+            text: Text::from_sections(sections).with_justify(JustifyText::Left),
+            text_anchor,
+            transform: Transform::from_translation(text_position),
+            ..default()
+        },
+    ));
+}
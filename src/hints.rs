@@ -0,0 +1,67 @@
+use crate::game_state::Game;
+use crate::grid::GridConfig;
+use crate::simulation::WAIT;
+use bevy::color::palettes::css::WHITE;
+use bevy::prelude::*;
+
+/// Hints a player can ask for in a game, unlimited when `None`.
+#[derive(Debug, Resource, Default)]
+pub struct HintLimit(pub Option<u32>);
+
+/// Move suggested to the player, shown until the next turn.
+#[derive(Debug, Resource, Default)]
+pub struct ShownHint(Option<Hint>);
+
+#[derive(Debug, Clone, Copy)]
+struct Hint {
+    turn: u32,
+    from: IVec2,
+    direction: IVec2,
+}
+
+pub fn clear_hint(mut shown_hint: ResMut<ShownHint>) {
+    shown_hint.0 = None;
+}
+
+pub fn handle_hint_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut game: ResMut<Game>,
+    limit: Res<HintLimit>,
+    mut shown_hint: ResMut<ShownHint>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyH) {
+        return;
+    }
+    let Some(from) = game.player.as_ref().map(|player| player.pos) else {
+        return;
+    };
+    match game.hint(limit.0) {
+        Some(direction) => {
+            shown_hint.0 = Some(Hint {
+                turn: game.turn,
+                from,
+                direction,
+            })
+        }
+        None => info!("No hint available"),
+    }
+}
+
+pub fn draw_hint(
+    mut gizmos: Gizmos,
+    shown_hint: Res<ShownHint>,
+    game: Res<Game>,
+    grid: Res<GridConfig>,
+) {
+    let Some(hint) = shown_hint.0.filter(|hint| hint.turn == game.turn) else {
+        return;
+    };
+    let from = grid.sprite_position_translation(hint.from.as_vec2());
+    let to = grid.sprite_position_translation((hint.from + hint.direction).as_vec2());
+
+    // Waiting is shown by the circle alone
+    if hint.direction != WAIT {
+        gizmos.arrow_2d(from, to, WHITE);
+    }
+    gizmos.circle_2d(to, grid.cell_size() * 0.3, WHITE);
+}
@@ -1,66 +1,144 @@
 use crate::{
+    autopilot::{Autopilot, Demo},
     components::*,
-    sounds::{play_random_sound, play_sound},
-    AllAssets, GRID_SIZE,
+    game_state::Game,
+    replays::{Playback, Recording},
+    rng::GameRng,
+    settings::Settings,
+    simulation::{CharacterId, Outcome, WAIT},
+    sounds::play_random_sound,
+    AllAssets,
 };
 use bevy::prelude::*;
 
+/// How many directions can be queued while the player is moving, 0 to drop them.
+#[derive(Debug, Resource)]
+pub struct InputBuffer {
+    pub size: usize,
+}
+
+impl Default for InputBuffer {
+    fn default() -> Self {
+        Self { size: 2 }
+    }
+}
+
 pub fn handle_input_movement(
     mut commands: Commands,
     keyboard: Res<ButtonInput<KeyCode>>,
     touches: Res<Touches>,
-    mut positions: Query<(Entity, &Position, &mut Journey), (With<Player>, Without<Transition>)>,
-    mut bot_positions: Query<(Entity, &Position, &mut Journey), (With<Automated>, Without<Player>)>,
+    mut players: Query<&mut InputQueue, With<Player>>,
+    moving: Query<(), (With<Transition>, WithPlayerOrAutomated)>,
+    input_buffer: Res<InputBuffer>,
+    mut journeys: Query<(Entity, &mut Journey), WithPlayerOrAutomated>,
+    mut game: ResMut<Game>,
     assets: Res<AllAssets>,
+    mut rng: ResMut<GameRng>,
+    mut recording: ResMut<Recording>,
+    mut playback: Option<ResMut<Playback>>,
+    mut autopilot: ResMut<Autopilot>,
+    demo: Option<Res<Demo>>,
+    settings: Res<Settings>,
 ) {
-    let Some(direction) = keyboard_direction(&keyboard).or_else(|| touch_direction(touches)) else {
+    let Ok(mut queue) = players.get_single_mut() else {
         return;
     };
+    // The turn goes on while any duck moves, bots sent back included
+    let in_transition = !moving.is_empty();
+    let input = match playback.as_deref_mut() {
+        Some(playback) if !in_transition => playback.next_direction(),
+        Some(_) => None,
+        None if (autopilot.enabled || demo.is_some()) && !in_transition => {
+            autopilot.choose(&game, demo.is_some())
+        }
+        None if autopilot.enabled || demo.is_some() => None,
+        None => keyboard_direction(&keyboard).or_else(|| touch_direction(touches)),
+    };
+    if let Some(direction) = input {
+        if !in_transition || queue.0.len() < input_buffer.size {
+            queue.0.push_back(direction);
+        }
+    }
+    if in_transition {
+        return;
+    }
+    let Some(direction) = queue.0.pop_front() else {
+        return;
+    };
+    recording.replay.moves.push(direction);
 
-    for (entity, current_pos, mut journey) in positions.iter_mut() {
-        let next_pos =
-            Position((current_pos.0 + direction).clamp(IVec2::ZERO, GRID_SIZE.as_ivec2() - 1));
-
-        if next_pos != *current_pos {
-            commands
-                .entity(entity)
-                .insert(Transition::new(*current_pos, next_pos));
-
-            commands.spawn(play_random_sound(&assets.move_sound));
-
-            journey.path.push(*current_pos);
-
-            // TODO: Manage journey finished after transition
-            if journey_finished(&journey, &next_pos) {
-                commands.trigger_targets(JourneyFinished, entity);
-                commands.spawn(play_sound(&assets.coin_sound));
-            } else if just_reached_target(&journey, &next_pos) {
-                commands.spawn(play_sound(&assets.goal_sound));
+    let outcomes = game.step(direction, &mut rng.gameplay);
+    if outcomes.contains(&Outcome::Blocked) {
+        return;
+    }
+    commands.spawn(play_random_sound(
+        &assets.move_sound,
+        settings.sfx_volume(),
+        &mut rng.cosmetic,
+    ));
+
+    for &outcome in outcomes.iter() {
+        match outcome {
+            Outcome::Moved { id, from, to } => {
+                let mut transition = Transition::new(Position(from), Position(to));
+                if collides_head_on(&outcomes, id) {
+                    transition = transition.head_on();
+                }
+                commands
+                    .entity(character_entity(&journeys, id))
+                    .insert(transition);
             }
-
-            for (entity, bot_pos, mut bot_journey) in bot_positions.iter_mut() {
-                bot_journey.bot_index = (bot_journey.bot_index + 1) % bot_journey.path.len() as i32;
-
-                commands.entity(entity).insert(Transition::new(
-                    *bot_pos,
-                    bot_journey.path[bot_journey.bot_index as usize],
-                ));
+            Outcome::Collided { id, .. } => {
+                commands
+                    .entity(character_entity(&journeys, id))
+                    .insert(OnArrival::Collides);
+            }
+            Outcome::TargetReached { id } => {
+                commands
+                    .entity(character_entity(&journeys, id))
+                    .insert(OnArrival::ReachesTarget);
             }
+            Outcome::JourneyFinished { id } => {
+                commands
+                    .entity(character_entity(&journeys, id))
+                    .insert(OnArrival::FinishesJourney);
+            }
+            // Played by the observers of the arrival events
+            Outcome::Spawned { .. }
+            | Outcome::GameOver
+            | Outcome::Victory
+            | Outcome::NoSafeJourney
+            | Outcome::Blocked => {}
+        }
+    }
+
+    for (_, mut journey) in journeys.iter_mut() {
+        if let Some(character) = game.character(journey.id) {
+            journey.update(character);
         }
     }
 }
 
-pub fn journey_finished(journey: &Journey, current_pos: &Position) -> bool {
-    let has_reached_target = journey.path.contains(&journey.target_pos);
-    let back_to_start = *current_pos == journey.start_pos;
-    has_reached_target && back_to_start
+fn collides_head_on(outcomes: &[Outcome], character: CharacterId) -> bool {
+    outcomes.iter().any(|outcome| {
+        matches!(outcome, Outcome::Collided { id, head_on: true, .. } if *id == character)
+    })
 }
-pub fn just_reached_target(journey: &Journey, current_pos: &Position) -> bool {
-    let at_target = *current_pos == journey.target_pos;
-    let never_before = !journey.path.contains(&journey.target_pos);
-    at_target && never_before
+
+fn character_entity(
+    journeys: &Query<(Entity, &mut Journey), WithPlayerOrAutomated>,
+    id: CharacterId,
+) -> Entity {
+    journeys
+        .iter()
+        .find(|(_, journey)| journey.id == id)
+        .map(|(entity, _)| entity)
+        .expect("Character entity")
 }
 
+/// Pixels a touch can move and still count as a tap.
+const TAP_DISTANCE: f32 = 20.;
+
 const DIRECTIONS: [IVec2; 4] = [IVec2::Y, IVec2::NEG_Y, IVec2::NEG_X, IVec2::X];
 
 fn keyboard_direction(keyboard: &Res<ButtonInput<KeyCode>>) -> Option<IVec2> {
@@ -72,6 +150,9 @@ fn keyboard_direction(keyboard: &Res<ButtonInput<KeyCode>>) -> Option<IVec2> {
     ];
     const WASD_KEYS: [KeyCode; 4] = [KeyCode::KeyW, KeyCode::KeyS, KeyCode::KeyA, KeyCode::KeyD];
 
+    if keyboard.just_pressed(KeyCode::Space) {
+        return Some(WAIT);
+    }
     for keys in [ARROW_KEYS, WASD_KEYS] {
         if let Some((_, direction)) = keys
             .iter()
@@ -86,12 +167,12 @@ fn keyboard_direction(keyboard: &Res<ButtonInput<KeyCode>>) -> Option<IVec2> {
 
 fn touch_direction(touches: Res<Touches>) -> Option<IVec2> {
     let touch = touches.iter_just_released().next()?;
-    dbg!(&touch);
 
-    let mut direction = touch.distance().normalize_or_zero();
-    if direction == Vec2::ZERO {
-        return None;
+    // A tap without swiping waits in place
+    if touch.distance().length() < TAP_DISTANCE {
+        return Some(WAIT);
     }
+    let mut direction = touch.distance().normalize_or_zero();
 
     // The screen seems to swap these two
     direction.x = -direction.x;
@@ -0,0 +1,199 @@
+//! Handcrafted boards, written as plain text maps.
+//!
+//! Each line is a row of the grid, the top row first:
+//! - `.` is an empty cell and `#` a wall,
+//! - an uppercase letter is the start of a journey, the same lowercase letter
+//!   is its target. Journeys are played in alphabetical order, then random
+//!   ones are picked as usual.
+//! - a digit is the start of a bot already on the board, its loop is written
+//!   on a line after the map, like `bot 1 RRDLLU`: one move per character as
+//!   in replays, ending back on the start.
+//!
+//! Empty lines and lines starting with `//` are ignored.
+
+use crate::replays::{char_move, move_char};
+use bevy::math::{IVec2, UVec2};
+use std::collections::{BTreeMap, HashSet};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Level {
+    pub size: UVec2,
+    pub walls: HashSet<IVec2>,
+    /// Start and target positions of the first journeys, in order.
+    pub journeys: Vec<(IVec2, IVec2)>,
+    /// Cells of the loop of each bot placed on the board, from its start.
+    pub bots: Vec<Vec<IVec2>>,
+    /// Only the journeys of the level are played, the game is won once they
+    /// are all finished.
+    pub fixed_only: bool,
+}
+
+impl Level {
+    pub fn empty(size: UVec2) -> Self {
+        Self {
+            size,
+            ..Self::default()
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (bot_lines, rows): (Vec<&str>, Vec<&str>) = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with("//"))
+            .partition(|line| line.starts_with("bot "));
+
+        let width = rows.first().ok_or("Empty level")?.chars().count();
+        if rows.iter().any(|row| row.chars().count() != width) {
+            return Err("All the rows of a level must have the same length".to_string());
+        }
+        let size = UVec2::new(width as u32, rows.len() as u32);
+
+        let mut walls = HashSet::new();
+        let mut starts = BTreeMap::new();
+        let mut targets = BTreeMap::new();
+        let mut bot_starts = BTreeMap::new();
+
+        for (row_index, row) in rows.iter().enumerate() {
+            let y = (rows.len() - 1 - row_index) as i32;
+            for (x, cell) in row.chars().enumerate() {
+                let pos = IVec2::new(x as i32, y);
+                match cell {
+                    '.' => {}
+                    '#' => {
+                        walls.insert(pos);
+                    }
+                    'A'..='Z' => {
+                        if starts.insert(cell, pos).is_some() {
+                            return Err(format!("Journey '{cell}' starts twice"));
+                        }
+                    }
+                    'a'..='z' => {
+                        if targets.insert(cell.to_ascii_uppercase(), pos).is_some() {
+                            return Err(format!(
+                                "Journey '{}' has two targets",
+                                cell.to_ascii_uppercase()
+                            ));
+                        }
+                    }
+                    '1'..='9' => {
+                        if bot_starts.insert(cell, pos).is_some() {
+                            return Err(format!("Bot '{cell}' starts twice"));
+                        }
+                    }
+                    _ => return Err(format!("Unknown cell '{cell}'")),
+                }
+            }
+        }
+
+        let journeys = starts
+            .into_iter()
+            .map(|(name, start_pos)| {
+                let target_pos = targets
+                    .remove(&name)
+                    .ok_or(format!("Journey '{name}' has no target"))?;
+                Ok((start_pos, target_pos))
+            })
+            .collect::<Result<_, String>>()?;
+        if let Some(name) = targets.keys().next() {
+            return Err(format!("Journey '{name}' has no start"));
+        }
+
+        let mut bots = BTreeMap::new();
+        for line in bot_lines {
+            let invalid = || format!("Invalid bot '{line}'");
+            let (name, moves) = line["bot ".len()..]
+                .trim()
+                .split_once(' ')
+                .ok_or_else(invalid)?;
+            let mut name = name.chars();
+            let (Some(name), None) = (name.next(), name.next()) else {
+                return Err(invalid());
+            };
+            let start_pos = *bot_starts
+                .get(&name)
+                .ok_or(format!("Bot '{name}' is not on the map"))?;
+            let path = bot_path(start_pos, moves.trim(), size, &walls)
+                .map_err(|err| format!("Bot '{name}': {err}"))?;
+            if bots.insert(name, path).is_some() {
+                return Err(format!("Bot '{name}' has two loops"));
+            }
+        }
+        if let Some(name) = bot_starts.keys().find(|name| !bots.contains_key(name)) {
+            return Err(format!("Bot '{name}' has no loop"));
+        }
+
+        Ok(Self {
+            size,
+            walls,
+            journeys,
+            bots: bots.into_values().collect(),
+            fixed_only: false,
+        })
+    }
+
+    /// Writes the map back in the format read by [`Self::parse`], journeys
+    /// and bots named in their order.
+    pub fn encode(&self) -> String {
+        let mut rows = vec![vec!['.'; self.size.x as usize]; self.size.y as usize];
+        let height = self.size.y as i32;
+        let mut set = |pos: IVec2, cell: char| {
+            rows[(height - 1 - pos.y) as usize][pos.x as usize] = cell;
+        };
+        for &wall in self.walls.iter() {
+            set(wall, '#');
+        }
+        for (&(start_pos, target_pos), name) in self.journeys.iter().zip('A'..='Z') {
+            set(start_pos, name);
+            set(target_pos, name.to_ascii_lowercase());
+        }
+        for (path, name) in self.bots.iter().zip('1'..='9') {
+            set(path[0], name);
+        }
+
+        let mut text = String::new();
+        for row in rows {
+            text.extend(row);
+            text += "\n";
+        }
+        for (path, name) in self.bots.iter().zip('1'..='9') {
+            let moves: String = path
+                .iter()
+                .zip(path.iter().cycle().skip(1))
+                .map(|(&from, &to)| move_char(to - from))
+                .collect();
+            text += &format!("bot {name} {moves}\n");
+        }
+        text
+    }
+}
+
+/// Cells of a bot following `moves` from `start_pos`, each one before its
+/// move, the last move bringing it back to its start.
+fn bot_path(
+    start_pos: IVec2,
+    moves: &str,
+    size: UVec2,
+    walls: &HashSet<IVec2>,
+) -> Result<Vec<IVec2>, String> {
+    let mut path = Vec::new();
+    let mut pos = start_pos;
+    for c in moves.chars() {
+        let direction = char_move(c).ok_or(format!("unknown move '{c}'"))?;
+        path.push(pos);
+        pos += direction;
+        let on_board = pos.cmpge(IVec2::ZERO).all() && pos.cmplt(size.as_ivec2()).all();
+        if !on_board || walls.contains(&pos) {
+            return Err(format!("cannot move to {pos}"));
+        }
+    }
+    if path.is_empty() || pos != start_pos {
+        return Err("the loop must end on its start".to_string());
+    }
+    Ok(path)
+}
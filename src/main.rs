@@ -6,103 +6,450 @@
 
 use bevy::asset::AssetMetaCheck;
 use bevy::audio::AudioPlugin;
+use bevy::ecs::schedule::SystemConfigs;
 use bevy::prelude::*;
+use bevy::render::camera::ScalingMode;
 use bevy::window::WindowResolution;
+use std::path::PathBuf;
 
+mod arcade;
+mod autopilot;
 mod characters;
 mod components;
+mod cycles;
+mod daily;
+mod danger_overlay;
+mod debug_overlay;
 mod destroyed;
 mod draws;
 mod game_state;
+mod grid;
+mod high_scores;
+mod hints;
 mod inputs;
+mod levels;
+mod menu;
+mod modes;
 mod movements;
+mod pause;
+mod puzzles;
+mod replays;
+mod rng;
+mod routes;
 mod scores;
+mod settings;
+mod simulation;
 mod sounds;
+mod storage;
+mod title;
 mod tutorial;
 
-const WINDOW_SIZE: f32 = 600.;
+fn main() {
+    let settings = settings::Settings::load();
+    let playback = arg_value("--replay").and_then(|path| {
+        replays::Playback::load(&path)
+            .map_err(|err| eprintln!("Cannot load replay {path}: {err}"))
+            .ok()
+    });
+    // Replays are played on their own board
+    let level = match &playback {
+        Some(playback) => playback.replay.level.clone().and_then(|level| {
+            grid::GridConfig::new(level.size)
+                .map(|grid| (level, grid))
+                .map_err(|err| eprintln!("Cannot play the level of the replay: {err}"))
+                .ok()
+        }),
+        None => arg_value("--level").and_then(|path| {
+            levels::Level::load(&path)
+                .and_then(|level| grid::GridConfig::new(level.size).map(|grid| (level, grid)))
+                .map_err(|err| eprintln!("Cannot load level {path}: {err}"))
+                .ok()
+        }),
+    };
+    let (level, grid) = match level {
+        Some((level, grid)) => (Some(level), grid),
+        None => {
+            let grid = match playback
+                .as_ref()
+                .and_then(|playback| playback.replay.grid_size)
+            {
+                Some(size) => grid::GridConfig::new(size)
+                    .map_err(|err| eprintln!("{err}"))
+                    .ok(),
+                None => arg_value("--grid").and_then(|size| {
+                    grid::GridConfig::parse(&size)
+                        .map_err(|err| eprintln!("{err}"))
+                        .ok()
+                }),
+            };
+            (
+                None,
+                grid.unwrap_or_else(|| {
+                    grid::GridConfig::new(settings.board_size).unwrap_or_default()
+                }),
+            )
+        }
+    };
+    if let Some(games) = arg_value("--measure") {
+        let level = level.unwrap_or_else(|| levels::Level::empty(grid.size));
+        autopilot::measure_difficulty(&level, games.parse().unwrap_or(20));
+        return;
+    }
+    let autopilot = arg_value("--autopilot").and_then(|strength| {
+        autopilot::Strength::parse(&strength)
+            .map_err(|err| eprintln!("{err}"))
+            .ok()
+    });
 
-const GRID_SIZE: UVec2 = UVec2::new(6, 6);
-const CELL_SIZE: f32 = WINDOW_SIZE / 10.;
+    let mode = match &playback {
+        Some(playback) => Some(playback.replay.mode),
+        None => arg_value("--mode").and_then(|mode| {
+            modes::GameMode::parse(&mode)
+                .map_err(|err| eprintln!("{err}"))
+                .ok()
+        }),
+    };
 
-fn main() {
-    App::new()
-        .add_plugins(
-            DefaultPlugins
-                .set(AssetPlugin {
-                    // Wasm builds will check for meta files (that don't exist) if this isn't set.
-                    // This causes errors and even panics in web builds on itch.
-                    // See https://github.com/bevyengine/bevy_github_ci_template/issues/48.
-                    meta_check: AssetMetaCheck::Never,
-                    ..default()
-                })
-                .set(WindowPlugin {
-                    primary_window: Window {
-                        title: "Bevy Jam #5 - Cycles".to_string(),
-                        fit_canvas_to_parent: true,
-                        prevent_default_event_handling: true,
-                        resolution: WindowResolution::new(WINDOW_SIZE, WINDOW_SIZE),
-                        ..default()
-                    }
-                    .into(),
-                    ..default()
-                })
-                .set(AudioPlugin {
-                    global_volume: GlobalVolume::new(0.3),
+    let seed = match &playback {
+        Some(playback) => Some(playback.replay.seed),
+        None => rng::seed_from_args(),
+    };
+
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(AssetPlugin {
+                // Wasm builds will check for meta files (that don't exist) if this isn't set.
+                // This causes errors and even panics in web builds on itch.
+                // See https://github.com/bevyengine/bevy_github_ci_template/issues/48.
+                meta_check: AssetMetaCheck::Never,
+                ..default()
+            })
+            .set(WindowPlugin {
+                primary_window: Window {
+                    title: "Bevy Jam #5 - Cycles".to_string(),
+                    fit_canvas_to_parent: true,
+                    prevent_default_event_handling: true,
+                    resolution: WindowResolution::new(grid.window_size, grid.window_size),
                     ..default()
-                }),
-        )
-        .add_plugins(game_plugin)
-        .run();
+                }
+                .into(),
+                ..default()
+            })
+            .set(AudioPlugin {
+                global_volume: GlobalVolume::new(settings.global_volume()),
+                ..default()
+            }),
+    )
+    .insert_resource(seed.map(rng::GameRng::from_seed).unwrap_or_default())
+    .insert_resource(game_state::CurrentLevel(level))
+    .insert_resource(grid)
+    .insert_resource(settings)
+    .insert_resource(mode.unwrap_or_default())
+    .insert_resource(
+        arg_value("--input-buffer")
+            .and_then(|size| size.parse().ok())
+            .map(|size| inputs::InputBuffer { size })
+            .unwrap_or_default(),
+    )
+    .insert_resource(autopilot::Autopilot::new(
+        autopilot.unwrap_or_default(),
+        autopilot.is_some(),
+    ))
+    .insert_resource(hints::HintLimit(
+        arg_value("--hints").and_then(|limit| limit.parse().ok()),
+    ))
+    .insert_resource(replays::Recording {
+        path: arg_value("--record").map(PathBuf::from),
+        ..default()
+    })
+    .add_plugins(game_plugin);
+
+    if let Some(playback) = playback {
+        app.insert_resource(playback);
+    }
+    if let Some(path) = arg_value("--puzzles") {
+        match puzzles::PuzzlePack::load(&path) {
+            Ok(pack) => {
+                app.insert_resource(pack);
+            }
+            Err(err) => eprintln!("Cannot load puzzles {path}: {err}"),
+        }
+    }
+    app.run();
+}
+
+/// Value following `name` on the command line, e.g. `--seed 42`.
+pub fn arg_value(name: &str) -> Option<String> {
+    std::env::args().skip_while(|arg| arg != name).nth(1)
+}
+
+/// Ends a game left from the pause menu before its end, to restart it or to
+/// quit it. Games reaching their end are cleared up once the end screen is
+/// left instead.
+fn leave_game() -> SystemConfigs {
+    (
+        daily::finish_daily_run.run_if(resource_exists::<daily::DailyChallenge>),
+        replays::save_recording.run_if(not(resource_exists::<autopilot::Demo>)),
+        game_state::clear_up_game_entities,
+        replays::stop_playback,
+    )
+        .chain()
 }
 
 pub fn game_plugin(app: &mut App) {
     app.init_state::<game_state::GameState>()
+        .add_sub_state::<game_state::PauseState>()
         .enable_state_scoped_entities::<game_state::GameState>()
-        .add_systems(Startup, (setup_camera, scores::spawn_score_display))
+        .enable_state_scoped_entities::<game_state::PauseState>()
+        .add_systems(
+            Startup,
+            (
+                setup_camera,
+                scores::spawn_score_display,
+                replays::spawn_playback_display.run_if(resource_exists::<replays::Playback>),
+                game_state::skip_title.run_if(resource_exists::<replays::Playback>),
+                autopilot::spawn_autopilot_display,
+                debug_overlay::spawn_debug_overlay_display,
+            ),
+        )
+        .add_systems(
+            OnEnter(game_state::GameState::Title),
+            (
+                title::spawn_title_screen,
+                menu::reset_menu_cursor,
+                puzzles::leave_puzzle.run_if(resource_exists::<puzzles::PuzzleSession>),
+                daily::leave_daily_challenge.run_if(resource_exists::<daily::DailyChallenge>),
+            ),
+        )
+        .add_systems(
+            OnExit(game_state::GameState::Title),
+            (
+                game_state::new_game,
+                replays::apply_replay_rules.run_if(resource_exists::<replays::Playback>),
+            )
+                .chain(),
+        )
+        .add_systems(
+            OnEnter(game_state::GameState::Puzzles),
+            (
+                puzzles::leave_puzzle.run_if(resource_exists::<puzzles::PuzzleSession>),
+                puzzles::spawn_puzzle_screen,
+                menu::reset_menu_cursor,
+            )
+                .chain(),
+        )
+        .add_systems(OnExit(game_state::GameState::Puzzles), game_state::new_game)
+        .add_systems(
+            OnEnter(game_state::GameState::HighScores),
+            (
+                high_scores::spawn_high_scores_text,
+                title::spawn_back_item,
+                menu::reset_menu_cursor,
+            ),
+        )
+        .add_systems(
+            OnEnter(game_state::GameState::Settings),
+            (settings::spawn_settings_screen, menu::reset_menu_cursor),
+        )
+        .add_systems(
+            OnExit(game_state::GameState::Settings),
+            settings::save_settings,
+        )
         .add_systems(
             OnEnter(game_state::GameState::InGame),
-            characters::spawn_first_player,
+            (
+                daily::start_daily_game.run_if(resource_exists::<daily::DailyChallenge>),
+                (
+                    characters::spawn_level_bots,
+                    characters::spawn_first_player,
+                    replays::start_recording,
+                    hints::clear_hint,
+                    pause::spawn_pause_button,
+                    arcade::reset_arcade_clock,
+                    game_state::forget_game_cycles,
+                ),
+            )
+                .chain(),
+        )
+        .add_systems(
+            OnEnter(game_state::PauseState::Paused),
+            (pause::spawn_pause_menu, menu::reset_menu_cursor),
         )
         .add_systems(
             OnEnter(game_state::GameState::EndGame),
-            game_state::spawn_restart_text,
+            (
+                puzzles::record_puzzle_result.run_if(
+                    resource_exists::<puzzles::PuzzleSession>
+                        .and_then(not(resource_exists::<autopilot::Demo>))
+                        .and_then(not(resource_exists::<replays::Playback>)),
+                ),
+                daily::finish_daily_run.run_if(resource_exists::<daily::DailyChallenge>),
+                game_state::spawn_restart_text,
+                high_scores::record_high_score.run_if(
+                    not(resource_exists::<autopilot::Demo>)
+                        .and_then(not(resource_exists::<replays::Playback>))
+                        .and_then(not(resource_exists::<puzzles::PuzzleSession>))
+                        .and_then(not(resource_exists::<daily::DailyChallenge>)),
+                ),
+                high_scores::spawn_high_scores_text.run_if(
+                    not(resource_exists::<puzzles::PuzzleSession>)
+                        .and_then(not(resource_exists::<daily::DailyChallenge>)),
+                ),
+                daily::spawn_daily_results_text.run_if(resource_exists::<daily::DailyChallenge>),
+                replays::save_recording.run_if(not(resource_exists::<autopilot::Demo>)),
+                autopilot::stop_demo,
+            )
+                .chain(),
         )
         .add_systems(
             Update,
             (
                 // logic
                 (
-                    inputs::handle_input_movement.run_if(in_state(game_state::GameState::InGame)),
+                    replays::control_playback.run_if(resource_exists::<replays::Playback>),
+                    inputs::handle_input_movement.run_if(in_state(game_state::PauseState::Running)),
+                    hints::handle_hint_input.run_if(in_state(game_state::PauseState::Running)),
+                    arcade::tick_arcade_clock.run_if(
+                        in_state(game_state::PauseState::Running)
+                            .and_then(not(resource_exists::<replays::Playback>)),
+                    ),
+                    arcade::end_arcade_replay.run_if(
+                        in_state(game_state::PauseState::Running)
+                            .and_then(resource_exists::<replays::Playback>),
+                    ),
+                    autopilot::toggle_autopilot,
+                    debug_overlay::toggle_debug_overlay,
+                    danger_overlay::toggle_danger_overlay,
+                    autopilot::start_demo_when_idle
+                        .run_if(in_state(game_state::GameState::EndGame)),
+                    autopilot::stop_demo_on_input.run_if(
+                        in_state(game_state::GameState::InGame)
+                            .and_then(resource_exists::<autopilot::Demo>),
+                    ),
                     game_state::handle_restart_input
                         .run_if(in_state(game_state::GameState::EndGame)),
-                    movements::move_transit_entities,
-                    movements::detect_collisions,
-                    destroyed::destroyed_animation,
+                    (
+                        movements::move_transit_entities,
+                        destroyed::destroyed_animation,
+                    )
+                        .run_if(not(in_state(game_state::PauseState::Paused))),
                     tutorial::validate_first_tutorial,
                     scores::update_score_display,
+                    replays::update_playback_display,
+                    autopilot::update_autopilot_display,
                 ),
                 // drawing
-                movements::position_to_transform,
-                (draws::draw_grid, draws::draw_paths),
-                draws::draw_targets,
+                (
+                    movements::position_to_transform,
+                    game_state::update_game_cycles.run_if(
+                        danger_overlay::danger_overlay_enabled
+                            .or_else(debug_overlay::debug_overlay_enabled),
+                    ),
+                ),
+                (
+                    draws::draw_grid.run_if(
+                        in_state(game_state::GameState::InGame)
+                            .or_else(in_state(game_state::GameState::EndGame)),
+                    ),
+                    draws::draw_paths,
+                    draws::draw_bot_behaviors,
+                ),
+                (
+                    draws::draw_targets,
+                    draws::draw_queued_moves,
+                    hints::draw_hint,
+                    debug_overlay::draw_debug_overlay,
+                    debug_overlay::update_debug_overlay_display,
+                    danger_overlay::draw_danger_overlay,
+                ),
             )
                 .chain(),
         )
+        .add_systems(
+            Update,
+            (
+                (pause::pause_on_input, pause::pause_on_focus_lost).run_if(
+                    in_state(game_state::PauseState::Running)
+                        .and_then(not(resource_exists::<autopilot::Demo>)),
+                ),
+                (
+                    pause::resume_on_escape,
+                    menu::navigate_menu.pipe(pause::choose_pause_option),
+                )
+                    .run_if(in_state(game_state::PauseState::Paused)),
+                menu::navigate_menu
+                    .pipe(title::choose_title_option)
+                    .run_if(in_state(game_state::GameState::Title)),
+                menu::navigate_menu
+                    .pipe(title::leave_high_scores)
+                    .run_if(in_state(game_state::GameState::HighScores)),
+                menu::navigate_menu
+                    .pipe(settings::choose_settings_option)
+                    .run_if(in_state(game_state::GameState::Settings)),
+                menu::navigate_menu
+                    .pipe(puzzles::choose_puzzle)
+                    .run_if(in_state(game_state::GameState::Puzzles)),
+                menu::highlight_menu_items,
+                settings::apply_settings,
+            ),
+        )
+        .add_systems(
+            OnTransition {
+                exited: game_state::GameState::InGame,
+                entered: game_state::GameState::Title,
+            },
+            leave_game(),
+        )
+        .add_systems(
+            OnEnter(game_state::GameState::Restarting),
+            (leave_game(), game_state::finish_restart).chain(),
+        )
         .add_systems(
             OnExit(game_state::GameState::EndGame),
-            game_state::clear_up_game_entities,
+            (
+                game_state::clear_up_game_entities,
+                replays::stop_playback,
+                high_scores::clear_new_high_score,
+            ),
         )
+        .init_resource::<arcade::ArcadeClock>()
         .init_resource::<characters::Characters>()
-        .init_resource::<scores::Score>()
+        .init_resource::<game_state::CurrentLevel>()
+        .init_resource::<grid::GridConfig>()
+        .init_resource::<settings::Settings>()
+        .init_resource::<puzzles::PuzzlePack>()
+        .init_resource::<puzzles::PuzzleResults>()
+        .init_resource::<daily::DailyResults>()
+        .init_resource::<modes::GameMode>()
+        .init_resource::<game_state::Game>()
+        .init_resource::<game_state::GameCycles>()
         .init_resource::<AllAssets>()
+        .init_resource::<rng::GameRng>()
+        .init_resource::<replays::Recording>()
+        .init_resource::<inputs::InputBuffer>()
+        .init_resource::<autopilot::Autopilot>()
+        .init_resource::<hints::HintLimit>()
+        .init_resource::<hints::ShownHint>()
+        .init_resource::<high_scores::HighScores>()
+        .init_resource::<menu::MenuCursor>()
+        .init_resource::<debug_overlay::DebugOverlay>()
+        .init_resource::<danger_overlay::DangerOverlay>()
         .observe(characters::add_new_character_on_finished_journey)
         .observe(tutorial::spawn_first_tutorial)
-        .observe(scores::score_nb_journeys);
+        .observe(movements::send_back_collided_character)
+        .observe(arcade::add_journey_seconds)
+        .observe(arcade::remove_collision_seconds);
 }
 
-pub fn setup_camera(mut commands: Commands) {
-    commands.spawn(Camera2dBundle::default());
+/// Frames the whole window area, whatever the size of the grid inside.
+pub fn setup_camera(mut commands: Commands, grid: Res<grid::GridConfig>) {
+    let mut camera = Camera2dBundle::default();
+    camera.projection.scaling_mode = ScalingMode::AutoMin {
+        min_width: grid.window_size,
+        min_height: grid.window_size,
+    };
+    commands.spawn(camera);
 }
 
 #[derive(Debug, Resource)]
@@ -0,0 +1,109 @@
+//! Lists of texts to choose from, with the arrow keys and Enter or by touch.
+
+use bevy::color::palettes::css::{WHITE, YELLOW};
+use bevy::prelude::*;
+use bevy::text::TextLayoutInfo;
+
+const ITEM_SPACING: f32 = 36.;
+
+/// Entry of the menu on screen, there is only one menu at a time.
+#[derive(Debug, Component)]
+pub struct MenuItem(pub usize);
+
+/// Entry selected with the keyboard.
+#[derive(Debug, Resource, Default)]
+pub struct MenuCursor(pub usize);
+
+/// Texts of a menu, one under the other from `top`.
+pub fn menu_items(
+    labels: impl IntoIterator<Item = impl Into<String>>,
+    top: Vec3,
+) -> Vec<(MenuItem, Text2dBundle)> {
+    labels
+        .into_iter()
+        .enumerate()
+        .map(|(index, label)| {
+            (
+                MenuItem(index),
+                Text2dBundle {
+                    text: Text::from_section(
+                        label,
+                        TextStyle {
+                            font_size: 28.0,
+                            ..default()
+                        },
+                    )
+                    .with_justify(JustifyText::Center),
+                    transform: Transform::from_translation(
+                        top - Vec3::Y * ITEM_SPACING * index as f32,
+                    ),
+                    ..default()
+                },
+            )
+        })
+        .collect()
+}
+
+pub fn reset_menu_cursor(mut cursor: ResMut<MenuCursor>) {
+    cursor.0 = 0;
+}
+
+/// Where a touch at `screen_position` in the window is in the world.
+pub fn touch_world_position(
+    cameras: &Query<(&Camera, &GlobalTransform)>,
+    screen_position: Vec2,
+) -> Option<Vec2> {
+    let (camera, camera_transform) = cameras.get_single().ok()?;
+    camera.viewport_to_world_2d(camera_transform, screen_position)
+}
+
+/// The centered text at `transform` covers `world_position`.
+pub fn text_contains(
+    transform: &GlobalTransform,
+    layout: &TextLayoutInfo,
+    world_position: Vec2,
+) -> bool {
+    Rect::from_center_size(transform.translation().truncate(), layout.logical_size)
+        .contains(world_position)
+}
+
+/// Moves the cursor, and returns the entry chosen this frame, by pressing
+/// Enter or by releasing a touch on it.
+pub fn navigate_menu(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    touches: Res<Touches>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    items: Query<(&MenuItem, &GlobalTransform, &TextLayoutInfo)>,
+    mut cursor: ResMut<MenuCursor>,
+) -> Option<usize> {
+    let len = items.iter().count();
+    if len == 0 {
+        return None;
+    }
+    if keyboard.any_just_pressed([KeyCode::ArrowUp, KeyCode::KeyW]) {
+        cursor.0 = (cursor.0 + len - 1) % len;
+    }
+    if keyboard.any_just_pressed([KeyCode::ArrowDown, KeyCode::KeyS]) {
+        cursor.0 = (cursor.0 + 1) % len;
+    }
+    if keyboard.any_just_pressed([KeyCode::Enter, KeyCode::Space]) {
+        return Some(cursor.0);
+    }
+
+    let touch = touches.iter_just_released().next()?;
+    let position = touch_world_position(&cameras, touch.position())?;
+    let (item, _, _) = items
+        .iter()
+        .find(|(_, transform, layout)| text_contains(transform, layout, position))?;
+    cursor.0 = item.0;
+    Some(item.0)
+}
+
+pub fn highlight_menu_items(mut items: Query<(&MenuItem, &mut Text)>, cursor: Res<MenuCursor>) {
+    for (item, mut text) in items.iter_mut() {
+        let color = if item.0 == cursor.0 { YELLOW } else { WHITE }.into();
+        if text.sections[0].style.color != color {
+            text.sections[0].style.color = color;
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use crate::simulation::BotBehavior;
+use bevy::prelude::*;
+
+/// Variant of the rules picked for the next games.
+#[derive(Debug, Resource, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GameMode {
+    /// Every bot loops on its journey, as in the original game.
+    #[default]
+    Classic,
+    /// Bots also bounce back and forth, park or move slowly.
+    Mixed,
+}
+
+impl GameMode {
+    pub const ALL: [Self; 2] = [Self::Classic, Self::Mixed];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Classic => "classic",
+            Self::Mixed => "mixed",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.name() == name)
+            .ok_or(format!("Unknown game mode '{name}'"))
+    }
+
+    /// Behaviors a bot can get when its journey is finished.
+    pub fn bot_behaviors(self) -> Vec<BotBehavior> {
+        match self {
+            Self::Classic => vec![BotBehavior::Loop],
+            Self::Mixed => vec![
+                BotBehavior::Loop,
+                BotBehavior::PingPong,
+                BotBehavior::Once,
+                BotBehavior::EveryNth(2),
+            ],
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Pausing the game in progress: with Escape, the button in the corner, or
+//! when the window loses the focus.
+
+use crate::game_state::{GameState, PauseState};
+use crate::grid::GridConfig;
+use crate::menu::{menu_items, text_contains, touch_world_position};
+use bevy::prelude::*;
+use bevy::text::TextLayoutInfo;
+use bevy::window::WindowFocused;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PauseOption {
+    Resume,
+    Restart,
+    /// Back to the title screen.
+    Quit,
+}
+
+impl PauseOption {
+    const ALL: [Self; 3] = [Self::Resume, Self::Restart, Self::Quit];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Resume => "Resume",
+            Self::Restart => "Restart",
+            Self::Quit => "Quit",
+        }
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct PauseButton;
+
+pub fn spawn_pause_button(mut commands: Commands, grid: Res<GridConfig>) {
+    let corner = grid.window_size / 2. - 24.;
+
+    commands.spawn((
+        PauseButton,
+        StateScoped(GameState::InGame),
+        Text2dBundle {
+            text: Text::from_section(
+                "II",
+                TextStyle {
+                    font_size: 28.0,
+                    ..default()
+                },
+            ),
+            transform: Transform::from_xyz(corner, corner, 0.),
+            ..default()
+        },
+    ));
+}
+
+pub fn pause_on_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    touches: Res<Touches>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    buttons: Query<(&GlobalTransform, &TextLayoutInfo), With<PauseButton>>,
+    mut next_state: ResMut<NextState<PauseState>>,
+) {
+    let touched = touches.iter_just_pressed().any(|touch| {
+        touch_world_position(&cameras, touch.position()).is_some_and(|position| {
+            buttons
+                .iter()
+                .any(|(transform, layout)| text_contains(transform, layout, position))
+        })
+    });
+    if keyboard.just_pressed(KeyCode::Escape) || touched {
+        next_state.set(PauseState::Paused);
+    }
+}
+
+pub fn pause_on_focus_lost(
+    mut focus_events: EventReader<WindowFocused>,
+    mut next_state: ResMut<NextState<PauseState>>,
+) {
+    if focus_events.read().any(|event| !event.focused) {
+        next_state.set(PauseState::Paused);
+    }
+}
+
+pub fn resume_on_escape(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<PauseState>>,
+) {
+    if keyboard.just_pressed(KeyCode::Escape) {
+        next_state.set(PauseState::Running);
+    }
+}
+
+pub fn spawn_pause_menu(mut commands: Commands, grid: Res<GridConfig>) {
+    commands.spawn((
+        StateScoped(PauseState::Paused),
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::BLACK.with_alpha(0.7),
+                custom_size: Some(Vec2::splat(grid.window_size)),
+                ..default()
+            },
+            transform: Transform::from_xyz(0., 0., 900.),
+            ..default()
+        },
+    ));
+    commands.spawn((
+        StateScoped(PauseState::Paused),
+        Text2dBundle {
+            text: Text::from_section(
+                "Paused",
+                TextStyle {
+                    font_size: 48.0,
+                    ..default()
+                },
+            ),
+            transform: Transform::from_xyz(0., 80., 901.),
+            ..default()
+        },
+    ));
+    for item in menu_items(
+        PauseOption::ALL.map(PauseOption::name),
+        Vec3::new(0., 0., 901.),
+    ) {
+        commands.spawn((StateScoped(PauseState::Paused), item));
+    }
+}
+
+pub fn choose_pause_option(
+    In(choice): In<Option<usize>>,
+    mut next_state: ResMut<NextState<PauseState>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    match choice.map(|index| PauseOption::ALL[index]) {
+        Some(PauseOption::Resume) => next_state.set(PauseState::Running),
+        Some(PauseOption::Restart) => game_state.set(GameState::Restarting),
+        Some(PauseOption::Quit) => game_state.set(GameState::Title),
+        None => {}
+    }
+}
@@ -0,0 +1,353 @@
+//! Puzzle mode: the handcrafted levels of a pack, each solved by finishing
+//! its journeys in as few moves as possible, unlocked one after the other.
+
+use crate::game_state::{CurrentLevel, Game, GameState};
+use crate::grid::GridConfig;
+use crate::levels::Level;
+use crate::menu::menu_items;
+use crate::storage::VersionedFile;
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Levels played when no other pack is given with `--puzzles`.
+const BUILT_IN_PACK: &str = include_str!("../levels/puzzles.txt");
+
+const RESULTS_FILE: VersionedFile = VersionedFile {
+    name: "puzzle results",
+    file_name: "puzzles.txt",
+    versions: 1..=1,
+};
+
+#[derive(Debug, Clone)]
+pub struct Puzzle {
+    pub name: String,
+    /// Moves of a good solution, to compare with.
+    pub par: u32,
+    pub level: Level,
+}
+
+/// Levels of the puzzle mode, in the order they unlock.
+///
+/// A pack is a text file where each level starts with its name and par,
+/// followed by its map as in [`Level::parse`]:
+/// ```text
+/// level First steps
+/// par 6
+/// .A..
+/// ....
+/// ..a.
+/// ....
+/// ```
+#[derive(Debug, Resource)]
+pub struct PuzzlePack {
+    pub puzzles: Vec<Puzzle>,
+}
+
+impl Default for PuzzlePack {
+    fn default() -> Self {
+        Self::parse(BUILT_IN_PACK).expect("Built-in puzzle pack")
+    }
+}
+
+impl PuzzlePack {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut blocks: Vec<(&str, Vec<&str>)> = Vec::new();
+        for line in text.lines() {
+            match (line.trim().strip_prefix("level "), blocks.last_mut()) {
+                (Some(name), _) => blocks.push((name.trim(), Vec::new())),
+                (None, Some((_, lines))) => lines.push(line),
+                (None, None) if line.trim().is_empty() || line.trim().starts_with("//") => {}
+                (None, None) => return Err(format!("Line outside of a level '{line}'")),
+            }
+        }
+
+        let puzzles: Vec<Puzzle> = blocks
+            .into_iter()
+            .map(|(name, lines)| {
+                Self::parse_puzzle(name, &lines).map_err(|err| format!("Level '{name}': {err}"))
+            })
+            .collect::<Result<_, _>>()?;
+        if puzzles.is_empty() {
+            return Err("Empty puzzle pack".to_string());
+        }
+        Ok(Self { puzzles })
+    }
+
+    fn parse_puzzle(name: &str, lines: &[&str]) -> Result<Puzzle, String> {
+        let (par_lines, map): (Vec<&str>, Vec<&str>) = lines
+            .iter()
+            .partition(|line| line.trim().starts_with("par "));
+        let [par] = par_lines[..] else {
+            return Err("One par is needed".to_string());
+        };
+        let par = par["par ".len()..]
+            .trim()
+            .parse()
+            .map_err(|_| format!("Invalid par '{par}'"))?;
+
+        let level = Level::parse(&map.join("\n"))?;
+        GridConfig::new(level.size)?;
+        if level.journeys.is_empty() {
+            return Err("No journey to play".to_string());
+        }
+        Ok(Puzzle {
+            name: name.to_string(),
+            par,
+            level: Level {
+                fixed_only: true,
+                ..level
+            },
+        })
+    }
+}
+
+/// Fewest moves that solved each level, by name, kept between sessions in the
+/// data directory.
+///
+/// Stored as a small text file, one level per line:
+/// ```text
+/// road-on-road puzzle results 1
+/// 14 First steps
+/// ```
+#[derive(Debug, Resource)]
+pub struct PuzzleResults {
+    pub best: HashMap<String, u32>,
+    path: Option<PathBuf>,
+}
+
+impl PuzzleResults {
+    pub fn encode(&self) -> String {
+        let mut best: Vec<_> = self.best.iter().collect();
+        best.sort();
+        let mut text = String::new();
+        for (name, moves) in best {
+            text += &format!("{moves} {name}\n");
+        }
+        RESULTS_FILE.encode(&text)
+    }
+
+    pub fn decode(text: &str) -> Result<HashMap<String, u32>, String> {
+        let (_, lines) = RESULTS_FILE.decode(text)?;
+        lines
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let invalid = || format!("Invalid puzzle result '{line}'");
+                let (moves, name) = line.trim().split_once(' ').ok_or_else(invalid)?;
+                Ok((name.to_string(), moves.parse().map_err(|_| invalid())?))
+            })
+            .collect()
+    }
+
+    /// Loads the results from the data directory, none if there are none yet.
+    pub fn load() -> Self {
+        let (path, best) = RESULTS_FILE.load(Self::decode);
+        let best = best.unwrap_or_default();
+        Self { best, path }
+    }
+
+    fn save(&self) {
+        RESULTS_FILE.save(self.path.as_deref(), &self.encode());
+    }
+
+    /// The first level is always open, the next ones once the previous one is
+    /// solved.
+    pub fn is_unlocked(&self, pack: &PuzzlePack, index: usize) -> bool {
+        index == 0 || self.best.contains_key(&pack.puzzles[index - 1].name)
+    }
+}
+
+impl FromWorld for PuzzleResults {
+    fn from_world(_world: &mut World) -> Self {
+        Self::load()
+    }
+}
+
+/// The puzzle being played, and the board to go back to afterwards.
+#[derive(Debug, Resource)]
+pub struct PuzzleSession {
+    pub puzzle: Puzzle,
+    previous_level: Option<Level>,
+    previous_grid: GridConfig,
+}
+
+impl PuzzleSession {
+    /// Journeys to finish to solve the level.
+    pub fn goal(&self) -> usize {
+        self.puzzle.level.journeys.len()
+    }
+
+    /// Title and reason of the end of `game`, replacing the usual ones.
+    pub fn ending(&self, game: &Game, results: &PuzzleResults) -> (&'static str, String) {
+        if !game.victory {
+            return ("Game ended", "Puzzle stopped".to_string());
+        }
+        let best = results.best.get(&self.puzzle.name).copied();
+        let best = best.map_or(String::new(), |best| format!(", best {best}"));
+        (
+            "Solved!",
+            format!("{} moves, par {}{best}", game.turn, self.puzzle.par),
+        )
+    }
+}
+
+fn puzzle_label(pack: &PuzzlePack, results: &PuzzleResults, index: usize) -> String {
+    let puzzle = &pack.puzzles[index];
+    if !results.is_unlocked(pack, index) {
+        return format!("{}. Locked", index + 1);
+    }
+    let best = match results.best.get(&puzzle.name) {
+        Some(best) => best.to_string(),
+        None => "-".to_string(),
+    };
+    format!(
+        "{}. {}, par {}, best {best}",
+        index + 1,
+        puzzle.name,
+        puzzle.par
+    )
+}
+
+pub fn spawn_puzzle_screen(
+    mut commands: Commands,
+    pack: Res<PuzzlePack>,
+    results: Res<PuzzleResults>,
+    grid: Res<GridConfig>,
+) {
+    let top = grid.window_size / 2. - 2. * grid.cell_size();
+    commands.spawn((
+        StateScoped(GameState::Puzzles),
+        Text2dBundle {
+            text: Text::from_section(
+                "Puzzles",
+                TextStyle {
+                    font_size: 48.0,
+                    ..default()
+                },
+            ),
+            transform: Transform::from_xyz(0., top, 0.),
+            ..default()
+        },
+    ));
+
+    let labels = (0..pack.puzzles.len())
+        .map(|index| puzzle_label(&pack, &results, index))
+        .chain(["Back".to_string()]);
+    for item in menu_items(labels, Vec3::new(0., top - 80., 0.)) {
+        commands.spawn((StateScoped(GameState::Puzzles), item));
+    }
+}
+
+/// Starts the chosen level if it is unlocked, on its own board.
+pub fn choose_puzzle(
+    In(choice): In<Option<usize>>,
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    pack: Res<PuzzlePack>,
+    results: Res<PuzzleResults>,
+    mut level: ResMut<CurrentLevel>,
+    mut grid: ResMut<GridConfig>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let index = match choice {
+        Some(index) if index < pack.puzzles.len() => index,
+        Some(_) => return next_state.set(GameState::Title),
+        None if keyboard.just_pressed(KeyCode::Escape) => return next_state.set(GameState::Title),
+        None => return,
+    };
+    if !results.is_unlocked(&pack, index) {
+        return;
+    }
+
+    let puzzle = pack.puzzles[index].clone();
+    let previous_level = level.0.replace(puzzle.level.clone());
+    let previous_grid = *grid;
+    *grid = GridConfig::new(puzzle.level.size).expect("Puzzle grid size");
+    commands.insert_resource(PuzzleSession {
+        puzzle,
+        previous_level,
+        previous_grid,
+    });
+    next_state.set(GameState::InGame);
+}
+
+/// Puts back the board played before the puzzle.
+pub fn leave_puzzle(
+    mut commands: Commands,
+    session: Res<PuzzleSession>,
+    mut level: ResMut<CurrentLevel>,
+    mut grid: ResMut<GridConfig>,
+) {
+    level.0.clone_from(&session.previous_level);
+    *grid = session.previous_grid;
+    commands.remove_resource::<PuzzleSession>();
+}
+
+/// Keeps the fewest moves of the level once it is solved.
+pub fn record_puzzle_result(
+    mut results: ResMut<PuzzleResults>,
+    session: Res<PuzzleSession>,
+    game: Res<Game>,
+) {
+    if !game.victory {
+        return;
+    }
+    let name = &session.puzzle.name;
+    if results
+        .best
+        .get(name)
+        .is_some_and(|&best| best <= game.turn)
+    {
+        return;
+    }
+    results.best.insert(name.clone(), game.turn);
+    results.save();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn built_in_pack_is_playable() {
+        let pack = PuzzlePack::parse(BUILT_IN_PACK).expect("Built-in puzzle pack");
+
+        assert_eq!(pack.puzzles[0].name, "First steps");
+        assert_eq!(pack.puzzles[0].par, 8);
+        // Results are kept by name
+        let names: HashSet<&str> = pack.puzzles.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names.len(), pack.puzzles.len());
+        for puzzle in pack.puzzles.iter() {
+            assert!(puzzle.level.fixed_only, "{}", puzzle.name);
+            assert!(!puzzle.level.journeys.is_empty(), "{}", puzzle.name);
+        }
+    }
+
+    #[test]
+    fn malformed_packs_are_refused() {
+        let map = ".A..\n....\n..a.\n....\n";
+        assert!(PuzzlePack::parse(&format!("level Good\npar 3\n{map}")).is_ok());
+        for text in [
+            String::new(),
+            "// Only a comment\n".to_string(),
+            map.to_string(),
+            format!("level No par\n{map}"),
+            format!("level Two pars\npar 3\npar 4\n{map}"),
+            format!("level Bad par\npar few\n{map}"),
+            "level No journey\npar 3\n....\n....\n....\n....\n".to_string(),
+            "level Too small\npar 3\n.A.\n..a\n".to_string(),
+            format!(
+                "level Good\npar 3\n{map}\nlevel Bad map\npar 3\n{}",
+                map.replace('.', "?")
+            ),
+        ] {
+            assert!(PuzzlePack::parse(&text).is_err(), "{text:?}");
+        }
+    }
+}
@@ -0,0 +1,372 @@
+use crate::daily::{DailyChallenge, DAILY_MODE};
+use crate::game_state::{CurrentLevel, Game};
+use crate::grid::GridConfig;
+use crate::levels::Level;
+use crate::modes::GameMode;
+use crate::rng::GameRng;
+use crate::simulation::{Rules, WAIT};
+use bevy::prelude::*;
+use std::path::PathBuf;
+
+/// Header of the replay files, bumped when the format changes.
+const REPLAY_HEADER: &str = "road-on-road replay";
+const REPLAY_VERSION: u32 = 5;
+
+const FAST_FORWARD_SPEED: f32 = 4.;
+
+/// A whole game: the seed, mode, rules and board it started from and every
+/// direction played, in order.
+///
+/// Stored as a small text file, the level map if any on `level` lines, then
+/// one move per character, `W` for a wait:
+/// ```text
+/// road-on-road replay 5
+/// seed 1234
+/// mode classic
+/// rules attempts
+/// attempts 20
+/// grid 4x4
+/// level .A..
+/// level ....
+/// level ..a.
+/// level ....
+/// UURWRDL
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Replay {
+    pub seed: u64,
+    pub mode: GameMode,
+    /// Rules and attempts of the game, unknown before version 5.
+    pub rules: Option<Rules>,
+    pub attempts: Option<u32>,
+    /// Size of the board, unknown before version 4.
+    pub grid_size: Option<UVec2>,
+    pub level: Option<Level>,
+    pub moves: Vec<IVec2>,
+}
+
+impl Replay {
+    pub fn encode(&self) -> String {
+        let moves: String = self
+            .moves
+            .iter()
+            .map(|&direction| move_char(direction))
+            .collect();
+        let mut text = format!(
+            "{REPLAY_HEADER} {REPLAY_VERSION}\nseed {}\nmode {}\n",
+            self.seed,
+            self.mode.name()
+        );
+        if let Some(rules) = self.rules {
+            text += &format!("rules {}\n", rules.name());
+        }
+        if let Some(attempts) = self.attempts {
+            text += &format!("attempts {attempts}\n");
+        }
+        if let Some(size) = self.grid_size {
+            text += &format!("grid {}x{}\n", size.x, size.y);
+        }
+        if let Some(level) = &self.level {
+            if level.fixed_only {
+                text += "fixed-only\n";
+            }
+            for line in level.encode().lines() {
+                text += &format!("level {line}\n");
+            }
+        }
+        text + &moves + "\n"
+    }
+
+    pub fn decode(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines().peekable();
+
+        let version = lines
+            .next()
+            .and_then(|line| line.strip_prefix(REPLAY_HEADER))
+            .ok_or("Not a replay file")?;
+        // Version 1 has no waits, versions 1 and 2 no mode, always classic,
+        // versions 1 to 3 no board and versions 1 to 4 no rules
+        match version.trim().parse() {
+            Ok(1..=REPLAY_VERSION) => {}
+            _ => return Err(format!("Unsupported replay version {}", version.trim())),
+        }
+
+        let seed = lines
+            .next()
+            .and_then(|line| line.strip_prefix("seed "))
+            .and_then(|seed| seed.trim().parse().ok())
+            .ok_or("Missing replay seed")?;
+
+        let mode = match lines.next_if(|line| line.starts_with("mode ")) {
+            Some(line) => GameMode::parse(line.trim_start_matches("mode ").trim())?,
+            None => GameMode::Classic,
+        };
+
+        let rules = match lines.next_if(|line| line.starts_with("rules ")) {
+            Some(line) => Some(Rules::parse(line.trim_start_matches("rules ").trim())?),
+            None => None,
+        };
+        let attempts = match lines.next_if(|line| line.starts_with("attempts ")) {
+            Some(line) => Some(
+                line.trim_start_matches("attempts ")
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("Invalid replay attempts '{line}'"))?,
+            ),
+            None => None,
+        };
+
+        let grid_size = match lines.next_if(|line| line.starts_with("grid ")) {
+            Some(line) => Some(GridConfig::parse(line.trim_start_matches("grid "))?.size),
+            None => None,
+        };
+
+        let fixed_only = lines.next_if(|line| line.trim() == "fixed-only").is_some();
+        let mut map = Vec::new();
+        while let Some(line) = lines.next_if(|line| line.starts_with("level ")) {
+            map.push(&line["level ".len()..]);
+        }
+        let level = if map.is_empty() {
+            None
+        } else {
+            Some(Level {
+                fixed_only,
+                ..Level::parse(&map.join("\n"))?
+            })
+        };
+
+        let moves = lines
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .chars()
+            .map(|c| char_move(c).ok_or(format!("Unknown move '{c}'")))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            seed,
+            mode,
+            rules,
+            attempts,
+            grid_size,
+            level,
+            moves,
+        })
+    }
+}
+
+pub fn move_char(direction: IVec2) -> char {
+    match direction {
+        IVec2::Y => 'U',
+        IVec2::NEG_Y => 'D',
+        IVec2::NEG_X => 'L',
+        IVec2::X => 'R',
+        WAIT => 'W',
+        _ => unreachable!("Not a direction: {direction}"),
+    }
+}
+
+pub fn char_move(c: char) -> Option<IVec2> {
+    match c {
+        'U' => Some(IVec2::Y),
+        'D' => Some(IVec2::NEG_Y),
+        'L' => Some(IVec2::NEG_X),
+        'R' => Some(IVec2::X),
+        'W' => Some(WAIT),
+        _ => None,
+    }
+}
+
+/// Moves of the current game, written to `path` when the game ends.
+#[derive(Debug, Resource, Default)]
+pub struct Recording {
+    pub path: Option<PathBuf>,
+    pub replay: Replay,
+}
+
+/// Replaces the player's inputs with the moves of a replay file.
+#[derive(Debug, Resource)]
+pub struct Playback {
+    pub replay: Replay,
+    pub cursor: usize,
+    pub paused: bool,
+    pub fast_forward: bool,
+    step: bool,
+}
+
+impl Playback {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        Ok(Self {
+            replay: Replay::decode(&text)?,
+            cursor: 0,
+            paused: false,
+            fast_forward: false,
+            step: false,
+        })
+    }
+
+    pub fn next_direction(&mut self) -> Option<IVec2> {
+        if self.paused && !std::mem::take(&mut self.step) {
+            return None;
+        }
+        let direction = self.replay.moves.get(self.cursor).copied()?;
+        self.cursor += 1;
+        Some(direction)
+    }
+
+    pub fn speed(&self) -> f32 {
+        if self.fast_forward {
+            FAST_FORWARD_SPEED
+        } else {
+            1.
+        }
+    }
+
+    fn text(&self) -> String {
+        let state = if self.paused { "Paused" } else { "Playing" };
+        format!(
+            "Replay {}/{} ({state})\nP: pause, N: step, F: fast-forward",
+            self.cursor,
+            self.replay.moves.len()
+        )
+    }
+}
+
+/// The replayed game keeps the rules it was recorded with, the settings are
+/// left as they are for the next games.
+pub fn apply_replay_rules(mut game: ResMut<Game>, playback: Res<Playback>) {
+    if let Some(rules) = playback.replay.rules {
+        game.rules = rules;
+    }
+    if let Some(attempts) = playback.replay.attempts {
+        game.attempts = attempts;
+        game.score.remaining_attempts = attempts;
+    }
+}
+
+/// The rules are the ones of the game, which can differ from the settings,
+/// and the daily challenge is always played on an empty board in its own
+/// mode, whatever the level and chosen mode.
+pub fn start_recording(
+    mut recording: ResMut<Recording>,
+    rng: Res<GameRng>,
+    mode: Res<GameMode>,
+    game: Res<Game>,
+    grid: Res<GridConfig>,
+    level: Res<CurrentLevel>,
+    daily: Option<Res<DailyChallenge>>,
+) {
+    recording.replay = Replay {
+        seed: rng.seed,
+        mode: if daily.is_some() { DAILY_MODE } else { *mode },
+        rules: Some(game.rules),
+        attempts: Some(game.attempts),
+        grid_size: Some(grid.size),
+        level: level.0.clone().filter(|_| daily.is_none()),
+        moves: Vec::new(),
+    };
+}
+
+pub fn save_recording(recording: Res<Recording>) {
+    let Some(path) = &recording.path else {
+        return;
+    };
+    match std::fs::write(path, recording.replay.encode()) {
+        Ok(()) => info!("Replay saved to {}", path.display()),
+        Err(err) => warn!("Cannot save replay to {}: {err}", path.display()),
+    }
+}
+
+/// Only the first game is replayed, the next ones are played with the keyboard.
+pub fn stop_playback(mut commands: Commands) {
+    commands.remove_resource::<Playback>();
+}
+
+pub fn control_playback(keyboard: Res<ButtonInput<KeyCode>>, mut playback: ResMut<Playback>) {
+    if keyboard.just_pressed(KeyCode::KeyP) {
+        playback.paused = !playback.paused;
+    }
+    if keyboard.just_pressed(KeyCode::KeyN) {
+        playback.paused = true;
+        playback.step = true;
+    }
+    if keyboard.just_pressed(KeyCode::KeyF) {
+        playback.fast_forward = !playback.fast_forward;
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct PlaybackDisplay;
+
+pub fn spawn_playback_display(
+    mut commands: Commands,
+    playback: Res<Playback>,
+    grid: Res<GridConfig>,
+) {
+    let text_position = Vec3::new(0., -(grid.window_size / 2. - 20.), 0.);
+
+    commands.spawn((
+        PlaybackDisplay,
+        Text2dBundle {
+            text: Text::from_section(
+                playback.text(),
+                TextStyle {
+                    font_size: 14.0,
+                    ..default()
+                },
+            )
+            .with_justify(JustifyText::Center),
+            transform: Transform::from_translation(text_position),
+            ..default()
+        },
+    ));
+}
+
+pub fn update_playback_display(
+    mut commands: Commands,
+    mut displays: Query<(Entity, &mut Text), With<PlaybackDisplay>>,
+    playback: Option<Res<Playback>>,
+) {
+    for (entity, mut text) in displays.iter_mut() {
+        match &playback {
+            Some(playback) => text.sections[0].value = playback.text(),
+            None => commands.entity(entity).despawn(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_keeps_its_board() {
+        let level = Level::parse("#A..\n.1..\n..a.\n....\nbot 1 RDLU").expect("Level");
+        let replay = Replay {
+            seed: 1234,
+            mode: GameMode::Classic,
+            rules: Some(Rules::Lives),
+            attempts: Some(5),
+            grid_size: Some(level.size),
+            level: Some(Level {
+                fixed_only: true,
+                ..level
+            }),
+            moves: vec![IVec2::Y, WAIT, IVec2::NEG_X],
+        };
+
+        assert_eq!(Replay::decode(&replay.encode()), Ok(replay));
+    }
+
+    #[test]
+    fn old_replays_have_no_board_nor_rules() {
+        let replay = Replay::decode("road-on-road replay 2\nseed 42\nUUL\n").expect("Replay");
+
+        assert_eq!(replay.mode, GameMode::Classic);
+        assert_eq!(replay.rules, None);
+        assert_eq!(replay.grid_size, None);
+        assert_eq!(replay.level, None);
+        assert_eq!(replay.moves, [IVec2::Y, IVec2::Y, IVec2::NEG_X]);
+    }
+}
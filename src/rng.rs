@@ -0,0 +1,45 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Mixed into the seed so the cosmetic stream never mirrors the gameplay one.
+const COSMETIC_SEED_SALT: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Every random decision of the game, split in two independent streams.
+///
+/// `gameplay` decides the board (spawns, journeys) and is the same for a given
+/// seed, while `cosmetic` is free to be used any number of times by sounds and
+/// animations without changing the gameplay sequence.
+#[derive(Debug, Resource)]
+pub struct GameRng {
+    pub seed: u64,
+    pub gameplay: StdRng,
+    pub cosmetic: StdRng,
+}
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            gameplay: StdRng::seed_from_u64(seed),
+            cosmetic: StdRng::seed_from_u64(seed ^ COSMETIC_SEED_SALT),
+        }
+    }
+
+    /// Seed of the next game, derived from the current one so a whole session
+    /// can be replayed from its first seed.
+    pub fn next_game(&mut self) {
+        *self = Self::from_seed(self.gameplay.gen());
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::from_seed(rand::thread_rng().gen())
+    }
+}
+
+/// Reads `--seed <number>` from the command line.
+pub fn seed_from_args() -> Option<u64> {
+    crate::arg_value("--seed")?.parse().ok()
+}
@@ -0,0 +1,242 @@
+//! Safe routes for the player through the looping bots.
+//!
+//! Bots replay their paths whatever the player does, so their positions are
+//! known for every upcoming turn. The board is searched as a time-expanded
+//! grid: a state is a cell at a turn, the turns looping once the bots are
+//! back to a previous state.
+
+use crate::simulation::{step_bots, Character, Outcome, Simulation, WAIT};
+use bevy::math::{IVec2, UVec2};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Longer cycles are only searched up to this turn, missing some routes.
+pub const MAX_SEARCH_TURNS: usize = 1_000;
+
+pub const DIRECTIONS: [IVec2; 4] = [IVec2::Y, IVec2::NEG_Y, IVec2::NEG_X, IVec2::X];
+
+/// A bot moves to the cell during the turn, even if it is then sent back.
+const BOT_ENDS_HERE: u8 = 1;
+
+/// Flag of a bot leaving the cell in `direction`, hit head-on by the opposite
+/// move. Waiting bots are only on their cell.
+fn bot_leaves(direction: IVec2) -> u8 {
+    DIRECTIONS
+        .iter()
+        .position(|&other| other == direction)
+        .map_or(0, |index| 2 << index)
+}
+
+/// The board of a [`Simulation`] and where its bots will be on each turn.
+#[derive(Debug, Clone)]
+pub struct TimeGrid {
+    grid_size: UVec2,
+    /// Cells without a wall, by [`Self::index`] on turn 0.
+    free: Vec<bool>,
+    /// Where the bots can hit the player on each cell of each turn from now,
+    /// by [`Self::index`].
+    danger: Vec<u8>,
+    /// Turns searched.
+    turns: usize,
+    /// Turn following the last one, when the bots have come back to it.
+    loop_start: Option<usize>,
+}
+
+impl TimeGrid {
+    pub fn new(simulation: &Simulation) -> Self {
+        Self::with_max_turns(simulation, MAX_SEARCH_TURNS)
+    }
+
+    /// Only looks `max_turns` ahead, for searches that do not need more.
+    pub fn with_max_turns(simulation: &Simulation, max_turns: usize) -> Self {
+        let grid_size = simulation.grid_size;
+        let cells = (grid_size.x * grid_size.y) as usize;
+        let cell = |pos: IVec2| (pos.y as u32 * grid_size.x + pos.x as u32) as usize;
+        let mut bots = simulation.bots.clone();
+        let mut seen_states = HashMap::new();
+        let mut danger = Vec::new();
+        let mut turns = 0;
+        let mut loop_start = None;
+
+        while turns < max_turns {
+            let state: Vec<usize> = bots.iter().map(|bot| bot.bot_phase).collect();
+            if let Some(&turn) = seen_states.get(&state) {
+                loop_start = Some(turn);
+                break;
+            }
+            seen_states.insert(state, turns);
+
+            let turn_danger = danger.len();
+            danger.resize(turn_danger + cells, 0);
+            for outcome in step_bots(&mut bots) {
+                if let Outcome::Moved { from, to, .. } = outcome {
+                    danger[turn_danger + cell(to)] |= BOT_ENDS_HERE;
+                    danger[turn_danger + cell(from)] |= bot_leaves(to - from);
+                }
+            }
+            turns += 1;
+        }
+
+        let mut free = vec![true; cells];
+        for &wall in simulation.walls.iter() {
+            free[cell(wall)] = false;
+        }
+
+        Self {
+            grid_size,
+            free,
+            danger,
+            turns,
+            loop_start,
+        }
+    }
+
+    /// Turns searched, at most the maximum given.
+    pub fn turns(&self) -> usize {
+        self.turns
+    }
+
+    fn next_turn(&self, turn: usize) -> Option<usize> {
+        match turn + 1 {
+            next if next < self.turns() => Some(next),
+            _ => self.loop_start,
+        }
+    }
+
+    fn previous_turns(&self, turn: usize) -> impl Iterator<Item = usize> {
+        let looped = (self.loop_start == Some(turn)).then(|| self.turns() - 1);
+        turn.checked_sub(1).into_iter().chain(looped)
+    }
+
+    fn is_free(&self, pos: IVec2) -> bool {
+        pos.cmpge(IVec2::ZERO).all()
+            && pos.cmplt(self.grid_size.as_ivec2()).all()
+            && self.free[self.index(pos, 0)]
+    }
+
+    /// Cells the player can be on after a turn on `pos`, waiting included.
+    fn neighbours(&self, pos: IVec2) -> impl Iterator<Item = IVec2> + '_ {
+        DIRECTIONS
+            .iter()
+            .chain([WAIT].iter())
+            .map(move |&direction| pos + direction)
+            .filter(|&next_pos| self.is_free(next_pos))
+    }
+
+    /// Moving `from` `to` during `turn` does not end on a bot or swap with one.
+    fn is_safe_move(&self, from: IVec2, to: IVec2, turn: usize) -> bool {
+        let danger = self.danger[self.index(to, turn)];
+        danger & (BOT_ENDS_HERE | bot_leaves(from - to)) == 0
+    }
+
+    fn index(&self, pos: IVec2, turn: usize) -> usize {
+        let cells = (self.grid_size.x * self.grid_size.y) as usize;
+        turn * cells + (pos.y as u32 * self.grid_size.x + pos.x as u32) as usize
+    }
+
+    /// States the player can reach from `start_pos` on the first turn.
+    fn reachable_from(&self, start_pos: IVec2) -> Vec<bool> {
+        let mut reached = vec![false; self.index(IVec2::ZERO, self.turns())];
+        reached[self.index(start_pos, 0)] = true;
+        let mut queue = VecDeque::from([(start_pos, 0)]);
+
+        while let Some((pos, turn)) = queue.pop_front() {
+            let Some(next_turn) = self.next_turn(turn) else {
+                continue;
+            };
+            for next_pos in self.neighbours(pos) {
+                let index = self.index(next_pos, next_turn);
+                if reached[index] || !self.is_safe_move(pos, next_pos, turn) {
+                    continue;
+                }
+                reached[index] = true;
+                queue.push_back((next_pos, next_turn));
+            }
+        }
+        reached
+    }
+
+    /// States from which the player can safely walk to `target_pos`.
+    fn leading_to(&self, target_pos: IVec2) -> Vec<bool> {
+        let mut leading = vec![false; self.index(IVec2::ZERO, self.turns())];
+        let mut queue = VecDeque::new();
+        for turn in 0..self.turns() {
+            leading[self.index(target_pos, turn)] = true;
+            queue.push_back((target_pos, turn));
+        }
+
+        while let Some((pos, turn)) = queue.pop_front() {
+            for previous_turn in self.previous_turns(turn) {
+                for previous_pos in self.neighbours(pos) {
+                    let index = self.index(previous_pos, previous_turn);
+                    if leading[index] || !self.is_safe_move(previous_pos, pos, previous_turn) {
+                        continue;
+                    }
+                    leading[index] = true;
+                    queue.push_back((previous_pos, previous_turn));
+                }
+            }
+        }
+        leading
+    }
+
+    /// Targets of a journey starting now on `start_pos` that can be reached
+    /// and left to come back to the start without being hit.
+    pub fn journey_targets(&self, start_pos: IVec2) -> HashSet<IVec2> {
+        if !self.is_free(start_pos) {
+            return HashSet::new();
+        }
+        let reached = self.reachable_from(start_pos);
+        let leading = self.leading_to(start_pos);
+
+        (0..self.grid_size.y as i32)
+            .flat_map(|y| (0..self.grid_size.x as i32).map(move |x| IVec2::new(x, y)))
+            .filter(|&pos| pos != start_pos)
+            .filter(|&pos| {
+                (0..self.turns()).any(|turn| {
+                    let index = self.index(pos, turn);
+                    reached[index] && leading[index]
+                })
+            })
+            .collect()
+    }
+
+    /// Moving the player in `direction`, or waiting, is possible now and does
+    /// not get it hit.
+    pub fn is_safe_first_move(&self, player: &Character, direction: IVec2) -> bool {
+        let next_pos = player.pos + direction;
+        self.is_free(next_pos) && self.is_safe_move(player.pos, next_pos, 0)
+    }
+
+    /// First direction of a shortest safe route finishing the journey of the
+    /// player: to its target if not reached yet, then back to its start.
+    pub fn next_safe_move(&self, player: &Character) -> Option<IVec2> {
+        let reached_target = player.has_reached_target() || player.pos == player.target_pos;
+        let state = |pos, turn, reached: bool| self.index(pos, turn) * 2 + reached as usize;
+
+        let mut visited = vec![false; state(IVec2::ZERO, self.turns(), false)];
+        visited[state(player.pos, 0, reached_target)] = true;
+        let mut queue = VecDeque::from([(player.pos, 0, reached_target, None)]);
+
+        while let Some((pos, turn, reached, first_move)) = queue.pop_front() {
+            let Some(next_turn) = self.next_turn(turn) else {
+                continue;
+            };
+            for next_pos in self.neighbours(pos) {
+                if !self.is_safe_move(pos, next_pos, turn) {
+                    continue;
+                }
+                let next_reached = reached || next_pos == player.target_pos;
+                let first_move = first_move.or(Some(next_pos - pos));
+                if next_reached && next_pos == player.start_pos {
+                    return first_move;
+                }
+                let index = state(next_pos, next_turn, next_reached);
+                if !visited[index] {
+                    visited[index] = true;
+                    queue.push_back((next_pos, next_turn, next_reached, first_move));
+                }
+            }
+        }
+        None
+    }
+}
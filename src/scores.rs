@@ -1,42 +1,62 @@
-use crate::{components::*, CELL_SIZE, WINDOW_SIZE};
+use crate::{
+    arcade::ArcadeClock,
+    game_state::{Game, GameState},
+    grid::GridConfig,
+    hints::HintLimit,
+    puzzles::PuzzleSession,
+    simulation::{Rules, Simulation},
+};
 use bevy::prelude::*;
 
-#[derive(Debug, Resource)]
-pub struct Score {
-    pub nb_journeys: u32,
-    pub remaining_attempts: u32,
-}
-
-pub const NB_ATTEMPTS: u32 = 20;
-
-impl Default for Score {
-    fn default() -> Self {
-        Self {
-            nb_journeys: 0,
-            remaining_attempts: NB_ATTEMPTS,
-        }
+fn score_text(
+    game: &Simulation,
+    clock: &ArcadeClock,
+    puzzle: Option<&PuzzleSession>,
+    hint_limit: &HintLimit,
+) -> String {
+    let score = &game.score;
+    if let Some(puzzle) = puzzle {
+        return format!(
+            "Moves: {}, Par: {}, Journeys: {}/{}",
+            game.turn,
+            puzzle.puzzle.par,
+            score.nb_journeys,
+            puzzle.goal()
+        );
+    }
+    let mut text = match game.rules {
+        Rules::AttemptsPerJourney => format!("Remaining Attempts: {}", score.remaining_attempts),
+        Rules::Lives => format!("Lives: {}", score.remaining_attempts),
+        Rules::Unlimited => format!("Hits: {}", score.attempts_used),
+        Rules::Arcade => format!("Time: {}", clock.remaining.ceil()),
+    };
+    text += &format!(", Journeys: {}", score.nb_journeys);
+    if score.bonus > 0 {
+        text += &format!(", Bonus: {}", score.bonus);
+    }
+    match hint_limit.0 {
+        Some(limit) => text += &format!(", Hints: {}", limit.saturating_sub(score.hints_used)),
+        None if score.hints_used > 0 => text += &format!(", Hints used: {}", score.hints_used),
+        None => {}
     }
+    text
 }
 
-impl Score {
-    fn text(&self) -> String {
-        format!(
-            "Remaining Attempts: {}, Journeys: {}",
-            self.remaining_attempts, self.nb_journeys
-        )
-    }
+fn score_position(grid: &GridConfig) -> Vec3 {
+    Vec3::new(0., grid.window_size / 2. - grid.cell_size(), 0.)
 }
 
 #[derive(Debug, Component)]
 pub struct ScoreDisplay;
 
-pub fn score_nb_journeys(_trigger: Trigger<JourneyFinished>, mut score: ResMut<Score>) {
-    score.nb_journeys += 1
-}
-
-pub fn spawn_score_display(mut commands: Commands, score: Res<Score>) {
-    let text_position = Vec3::new(0., WINDOW_SIZE / 2. - CELL_SIZE, 0.);
-
+pub fn spawn_score_display(
+    mut commands: Commands,
+    game: Res<Game>,
+    clock: Res<ArcadeClock>,
+    puzzle: Option<Res<PuzzleSession>>,
+    hint_limit: Res<HintLimit>,
+    grid: Res<GridConfig>,
+) {
     let text_style = TextStyle {
         font_size: 20.0,
         ..default()
@@ -45,18 +65,35 @@ pub fn spawn_score_display(mut commands: Commands, score: Res<Score>) {
     commands.spawn((
         ScoreDisplay,
         Text2dBundle {
-            text: Text::from_section(score.text(), text_style).with_justify(JustifyText::Center),
-            transform: Transform::from_translation(text_position),
+            text: Text::from_section(
+                score_text(&game, &clock, puzzle.as_deref(), &hint_limit),
+                text_style,
+            )
+            .with_justify(JustifyText::Center),
+            transform: Transform::from_translation(score_position(&grid)),
             ..default()
         },
     ));
 }
 
+/// Keeps the score of the current or last game, hidden on the menus.
 pub fn update_score_display(
-    mut score_displays: Query<&mut Text, With<ScoreDisplay>>,
-    score: Res<Score>,
+    mut score_displays: Query<(&mut Text, &mut Transform, &mut Visibility), With<ScoreDisplay>>,
+    game: Res<Game>,
+    clock: Res<ArcadeClock>,
+    puzzle: Option<Res<PuzzleSession>>,
+    hint_limit: Res<HintLimit>,
+    grid: Res<GridConfig>,
+    state: Res<State<GameState>>,
 ) {
-    for mut text in score_displays.iter_mut() {
-        text.sections[0].value = score.text();
+    for (mut text, mut transform, mut visibility) in score_displays.iter_mut() {
+        *visibility = match state.get() {
+            GameState::InGame | GameState::EndGame | GameState::Restarting => Visibility::Inherited,
+            GameState::Title | GameState::HighScores | GameState::Settings | GameState::Puzzles => {
+                Visibility::Hidden
+            }
+        };
+        text.sections[0].value = score_text(&game, &clock, puzzle.as_deref(), &hint_limit);
+        transform.translation = score_position(&grid);
     }
 }
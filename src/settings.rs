@@ -0,0 +1,323 @@
+//! Options of the player, changed on the settings screen and saved in the
+//! data directory.
+
+use crate::game_state::{CurrentLevel, GameState};
+use crate::grid::{GridConfig, MAX_GRID_SIZE, MIN_GRID_SIZE};
+use crate::menu::{menu_items, MenuCursor, MenuItem};
+use crate::simulation::{Rules, NB_ATTEMPTS};
+use crate::storage::VersionedFile;
+use bevy::audio::Volume;
+use bevy::prelude::*;
+use std::path::PathBuf;
+
+/// Bumped when the format changes, settings missing from older files keep their
+/// default.
+const SETTINGS_FILE: VersionedFile = VersionedFile {
+    name: "settings",
+    file_name: "settings.txt",
+    versions: 1..=2,
+};
+
+const VOLUME_CHOICES: [u32; 11] = [0, 10, 20, 30, 40, 50, 60, 70, 80, 90, 100];
+const ATTEMPTS_CHOICES: [u32; 6] = [1, 3, 5, 10, NB_ATTEMPTS, 50];
+const ANIMATION_SPEED_CHOICES: [u32; 5] = [50, 100, 150, 200, 300];
+
+/// Stored as a small text file, one setting per line, volumes and speed in
+/// percents:
+/// ```text
+/// road-on-road settings 2
+/// master_volume 30
+/// sfx_volume 100
+/// muted false
+/// rules attempts
+/// attempts 20
+/// animation_speed 100
+/// board_size 6x6
+/// ```
+#[derive(Debug, Resource, Clone, PartialEq)]
+pub struct Settings {
+    pub master_volume: u32,
+    pub sfx_volume: u32,
+    pub muted: bool,
+    pub rules: Rules,
+    /// Attempts given for each journey, or lives for the whole game,
+    /// depending on the rules.
+    pub attempts: u32,
+    /// Percent of the normal speed of the moves, never 0.
+    pub animation_speed: u32,
+    /// Size of the random boards.
+    pub board_size: UVec2,
+    path: Option<PathBuf>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 30,
+            sfx_volume: 100,
+            muted: false,
+            rules: Rules::default(),
+            attempts: NB_ATTEMPTS,
+            animation_speed: 100,
+            board_size: GridConfig::default().size,
+            path: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn encode(&self) -> String {
+        SETTINGS_FILE.encode(&format!(
+            "master_volume {}\nsfx_volume {}\nmuted {}\nrules {}\nattempts {}\n\
+            animation_speed {}\nboard_size {}x{}\n",
+            self.master_volume,
+            self.sfx_volume,
+            self.muted,
+            self.rules.name(),
+            self.attempts,
+            self.animation_speed,
+            self.board_size.x,
+            self.board_size.y,
+        ))
+    }
+
+    /// Reads the settings of `text`, the missing ones keep their default.
+    pub fn decode(text: &str) -> Result<Self, String> {
+        let (_, lines) = SETTINGS_FILE.decode(text)?;
+        let mut settings = Self::default();
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let invalid = || format!("Invalid setting '{line}'");
+            let (name, value) = line.trim().split_once(' ').ok_or_else(invalid)?;
+            match name {
+                "master_volume" => settings.master_volume = value.parse().map_err(|_| invalid())?,
+                "sfx_volume" => settings.sfx_volume = value.parse().map_err(|_| invalid())?,
+                "muted" => settings.muted = value.parse().map_err(|_| invalid())?,
+                "rules" => settings.rules = Rules::parse(value)?,
+                "attempts" => {
+                    settings.attempts = value
+                        .parse()
+                        .ok()
+                        .filter(|&attempts| attempts > 0)
+                        .ok_or_else(invalid)?
+                }
+                "animation_speed" => {
+                    settings.animation_speed = value
+                        .parse()
+                        .ok()
+                        .filter(|&speed| speed > 0)
+                        .ok_or_else(invalid)?
+                }
+                "board_size" => settings.board_size = GridConfig::parse(value)?.size,
+                _ => return Err(format!("Unknown setting '{name}'")),
+            }
+        }
+        Ok(settings)
+    }
+
+    /// Loads the settings from the data directory, the defaults if there are
+    /// none yet or they are unreadable.
+    pub fn load() -> Self {
+        let (path, settings) = SETTINGS_FILE.load(Self::decode);
+        let settings = settings.unwrap_or_default();
+        Self { path, ..settings }
+    }
+
+    fn save(&self) {
+        SETTINGS_FILE.save(self.path.as_deref(), &self.encode());
+    }
+
+    pub fn global_volume(&self) -> f32 {
+        if self.muted {
+            0.
+        } else {
+            self.master_volume as f32 / 100.
+        }
+    }
+
+    pub fn sfx_volume(&self) -> Volume {
+        Volume::new(self.sfx_volume as f32 / 100.)
+    }
+
+    pub fn animation_speed(&self) -> f32 {
+        self.animation_speed as f32 / 100.
+    }
+}
+
+/// Name of the rules on screen.
+pub fn rules_name(rules: Rules) -> &'static str {
+    match rules {
+        Rules::AttemptsPerJourney => "attempts per journey",
+        Rules::Lives => "lives",
+        Rules::Unlimited => "unlimited practice",
+        Rules::Arcade => "timed arcade",
+    }
+}
+
+/// The choice next to `current` in `choices`, `step` times forward or
+/// backward, around the list.
+fn cycle<T: Copy + PartialEq>(choices: &[T], current: T, step: i32) -> T {
+    let index = choices
+        .iter()
+        .position(|&choice| choice == current)
+        .unwrap_or_default();
+    choices[(index as i32 + step).rem_euclid(choices.len() as i32) as usize]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SettingsOption {
+    MasterVolume,
+    SfxVolume,
+    Mute,
+    Rules,
+    Attempts,
+    AnimationSpeed,
+    BoardSize,
+    Back,
+}
+
+impl SettingsOption {
+    const ALL: [Self; 8] = [
+        Self::MasterVolume,
+        Self::SfxVolume,
+        Self::Mute,
+        Self::Rules,
+        Self::Attempts,
+        Self::AnimationSpeed,
+        Self::BoardSize,
+        Self::Back,
+    ];
+
+    fn label(self, settings: &Settings) -> String {
+        match self {
+            Self::MasterVolume => format!("Volume: {}%", settings.master_volume),
+            Self::SfxVolume => format!("Sound effects: {}%", settings.sfx_volume),
+            Self::Mute => format!("Mute: {}", if settings.muted { "on" } else { "off" }),
+            Self::Rules => format!("Rules: {}", rules_name(settings.rules)),
+            Self::Attempts => match settings.rules {
+                Rules::AttemptsPerJourney => {
+                    format!("Attempts per journey: {}", settings.attempts)
+                }
+                Rules::Lives => format!("Lives: {}", settings.attempts),
+                Rules::Unlimited | Rules::Arcade => "Attempts: unlimited".to_string(),
+            },
+            Self::AnimationSpeed => format!("Animation speed: {}x", settings.animation_speed()),
+            Self::BoardSize => format!(
+                "Board size: {}x{}",
+                settings.board_size.x, settings.board_size.y
+            ),
+            Self::Back => "Back".to_string(),
+        }
+    }
+
+    fn change(self, settings: &mut Settings, step: i32) {
+        match self {
+            Self::MasterVolume => {
+                settings.master_volume = cycle(&VOLUME_CHOICES, settings.master_volume, step)
+            }
+            Self::SfxVolume => {
+                settings.sfx_volume = cycle(&VOLUME_CHOICES, settings.sfx_volume, step)
+            }
+            Self::Mute => settings.muted = !settings.muted,
+            Self::Rules => settings.rules = cycle(&Rules::ALL, settings.rules, step),
+            Self::Attempts => settings.attempts = cycle(&ATTEMPTS_CHOICES, settings.attempts, step),
+            Self::AnimationSpeed => {
+                settings.animation_speed =
+                    cycle(&ANIMATION_SPEED_CHOICES, settings.animation_speed, step)
+            }
+            Self::BoardSize => {
+                let sizes: Vec<u32> = (MIN_GRID_SIZE..=MAX_GRID_SIZE).collect();
+                settings.board_size = UVec2::splat(cycle(&sizes, settings.board_size.x, step));
+            }
+            Self::Back => {}
+        }
+    }
+}
+
+pub fn spawn_settings_screen(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    grid: Res<GridConfig>,
+) {
+    let top = Vec3::new(0., grid.window_size / 4., 0.);
+    let labels = SettingsOption::ALL.map(|option| option.label(&settings));
+
+    for item in menu_items(labels, top) {
+        commands.spawn((StateScoped(GameState::Settings), item));
+    }
+}
+
+/// Changes the selected setting with the side arrows, or to the next choice
+/// when chosen.
+pub fn choose_settings_option(
+    In(choice): In<Option<usize>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    cursor: Res<MenuCursor>,
+    mut items: Query<(&MenuItem, &mut Text)>,
+    mut settings: ResMut<Settings>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let step = match choice.map(|index| SettingsOption::ALL[index]) {
+        Some(SettingsOption::Back) => return next_state.set(GameState::Title),
+        Some(_) => 1,
+        None if keyboard.just_pressed(KeyCode::Escape) => return next_state.set(GameState::Title),
+        None if keyboard.just_pressed(KeyCode::ArrowRight) => 1,
+        None if keyboard.just_pressed(KeyCode::ArrowLeft) => -1,
+        None => return,
+    };
+
+    SettingsOption::ALL[cursor.0].change(&mut settings, step);
+    // The label of the attempts depends on the rules
+    for (item, mut text) in items.iter_mut() {
+        let label = SettingsOption::ALL[item.0].label(&settings);
+        if text.sections[0].value != label {
+            text.sections[0].value = label;
+        }
+    }
+}
+
+pub fn save_settings(settings: Res<Settings>) {
+    settings.save();
+}
+
+/// Applies the settings as soon as they change. The board size is only used
+/// by the next random board, not by handcrafted levels.
+pub fn apply_settings(
+    settings: Res<Settings>,
+    mut global_volume: ResMut<GlobalVolume>,
+    mut grid: ResMut<GridConfig>,
+    level: Res<CurrentLevel>,
+) {
+    if !settings.is_changed() || settings.is_added() {
+        return;
+    }
+    *global_volume = GlobalVolume::new(settings.global_volume());
+    if level.0.is_none() {
+        if let Ok(board) = GridConfig::new(settings.board_size) {
+            grid.set_if_neq(board);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_settings_are_read_back() {
+        let settings = Settings {
+            rules: Rules::Lives,
+            attempts: 3,
+            animation_speed: 200,
+            ..Settings::default()
+        };
+        assert_eq!(Settings::decode(&settings.encode()), Ok(settings));
+    }
+
+    #[test]
+    fn frozen_animations_are_refused() {
+        let text = Settings::default()
+            .encode()
+            .replace("animation_speed 100", "animation_speed 0");
+        assert!(Settings::decode(&text).is_err());
+    }
+}
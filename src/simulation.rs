@@ -0,0 +1,918 @@
+//! Rules of the game on the logical grid, without any ECS, rendering or audio.
+//!
+//! Only Bevy's math types are used here, so a whole game can be played and
+//! inspected from plain Rust code. The Bevy systems translate inputs into
+//! [`Simulation::step`] calls and play back the returned [`Outcome`]s.
+
+use crate::levels::Level;
+use crate::routes::TimeGrid;
+use bevy::math::{IVec2, UVec2};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+pub const NB_ATTEMPTS: u32 = 20;
+
+/// Points added to the journeys when the board is filled.
+pub const VICTORY_BONUS: u32 = 10;
+
+/// Turns a new journey must fit in. Spawns only search that far ahead, so
+/// they stay quick on crowded boards.
+const SPAWN_SEARCH_TURNS: usize = 250;
+
+pub type CharacterId = u32;
+
+/// Size of the ducks placed by a level, relative to a cell.
+const LEVEL_BOT_SCALE: f32 = 0.75;
+
+/// Direction of a player staying on its cell for a turn.
+pub const WAIT: IVec2 = IVec2::ZERO;
+
+/// How a bot replays the path of its journey.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BotBehavior {
+    /// Around its loop, again and again.
+    #[default]
+    Loop,
+    /// Forward along its path, then backward to its start.
+    PingPong,
+    /// Around its loop once, then parked on its start for good.
+    Once,
+    /// Around its loop, moving only once every `n` turns.
+    EveryNth(u32),
+}
+
+impl BotBehavior {
+    /// Number of phases of a bot on a path of `len` cells, after which it
+    /// moves the same way again. A parked bot stays on its last phase.
+    pub fn period(self, len: usize) -> usize {
+        match self {
+            Self::Loop => len,
+            Self::PingPong => (2 * len.saturating_sub(1)).max(1),
+            Self::Once => len + 1,
+            Self::EveryNth(n) => n.max(1) as usize * len,
+        }
+    }
+
+    /// Phase of a bot on a path of `len` cells, `turns` after `phase`.
+    pub fn phase_after(self, phase: usize, turns: usize, len: usize) -> usize {
+        match self {
+            Self::Once => phase.saturating_add(turns).min(len),
+            _ => (phase + turns % self.period(len)) % self.period(len),
+        }
+    }
+
+    /// Index in its path of the cell a bot is on at `phase`.
+    pub fn path_index(self, phase: usize, len: usize) -> usize {
+        match self {
+            Self::Loop => phase,
+            Self::PingPong if phase < len => phase,
+            Self::PingPong => 2 * (len - 1) - phase,
+            Self::Once => phase % len,
+            Self::EveryNth(n) => phase / n.max(1) as usize,
+        }
+    }
+}
+
+/// A duck on the grid, either the player or a bot replaying a finished journey.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Character {
+    pub id: CharacterId,
+    pub pos: IVec2,
+    pub start_pos: IVec2,
+    pub target_pos: IVec2,
+    pub path: Vec<IVec2>,
+    pub bot_index: usize,
+    pub behavior: BotBehavior,
+    /// Turns played by the bot since its start, wrapped around its period.
+    pub bot_phase: usize,
+    /// Size of its duck relative to a cell, drawn with its journey.
+    pub scale: f32,
+}
+
+impl Character {
+    fn new(id: CharacterId, start_pos: IVec2, target_pos: IVec2) -> Self {
+        Self {
+            id,
+            pos: start_pos,
+            start_pos,
+            target_pos,
+            path: Vec::new(),
+            bot_index: 0,
+            behavior: BotBehavior::default(),
+            bot_phase: 0,
+            scale: LEVEL_BOT_SCALE,
+        }
+    }
+
+    pub fn has_reached_target(&self) -> bool {
+        self.path.contains(&self.target_pos)
+    }
+
+    pub fn journey_finished(&self) -> bool {
+        self.has_reached_target() && self.pos == self.start_pos
+    }
+
+    fn respawn(&mut self) {
+        self.pos = self.start_pos;
+        self.bot_index = 0;
+        self.bot_phase = 0;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Score {
+    pub nb_journeys: u32,
+    pub remaining_attempts: u32,
+    /// Times the player was hit over the whole game.
+    pub attempts_used: u32,
+    pub hints_used: u32,
+    /// Points added to the journeys by a victory.
+    pub bonus: u32,
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        Self {
+            nb_journeys: 0,
+            remaining_attempts: NB_ATTEMPTS,
+            attempts_used: 0,
+            hints_used: 0,
+            bonus: 0,
+        }
+    }
+}
+
+/// How many times the player can be hit before the game is over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rules {
+    /// The attempts are given back on each new journey.
+    #[default]
+    AttemptsPerJourney,
+    /// The attempts are a pool of lives for the whole game.
+    Lives,
+    /// Practice, the game only ends when the board is full.
+    Unlimited,
+    /// Unlimited attempts against the clock, played out of the simulation.
+    Arcade,
+}
+
+impl Rules {
+    pub const ALL: [Self; 4] = [
+        Self::AttemptsPerJourney,
+        Self::Lives,
+        Self::Unlimited,
+        Self::Arcade,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::AttemptsPerJourney => "attempts",
+            Self::Lives => "lives",
+            Self::Unlimited => "unlimited",
+            Self::Arcade => "arcade",
+        }
+    }
+
+    /// The game ends when the player runs out of attempts.
+    pub fn counts_attempts(self) -> bool {
+        matches!(self, Self::AttemptsPerJourney | Self::Lives)
+    }
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|rules| rules.name() == name)
+            .ok_or(format!("Unknown rules '{name}'"))
+    }
+}
+
+/// What happened during a [`Simulation::step`], in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Moved {
+        id: CharacterId,
+        from: IVec2,
+        to: IVec2,
+    },
+    /// The player pushed against the border or a wall, nothing moved.
+    Blocked,
+    /// The character was hit and sent back to its start position. A head-on
+    /// collision happens halfway, when two characters swap their cells.
+    Collided {
+        id: CharacterId,
+        at: IVec2,
+        head_on: bool,
+    },
+    TargetReached {
+        id: CharacterId,
+    },
+    /// The player became a bot, a new player is spawned unless the game is over.
+    JourneyFinished {
+        id: CharacterId,
+    },
+    Spawned {
+        id: CharacterId,
+    },
+    /// The player ran out of attempts.
+    GameOver,
+    /// The board is full, no new journey is left for a player.
+    Victory,
+    /// Journeys are left on the board but none is safe, the game is over
+    /// without a victory.
+    NoSafeJourney,
+}
+
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    pub grid_size: UVec2,
+    pub walls: HashSet<IVec2>,
+    /// Journeys declared by the level, used before random ones.
+    pub fixed_journeys: VecDeque<(IVec2, IVec2)>,
+    /// No random journey follows the ones of the level.
+    pub fixed_only: bool,
+    /// Behaviors a bot can get when its journey is finished, picked at random.
+    pub bot_behaviors: Vec<BotBehavior>,
+    pub player: Option<Character>,
+    pub bots: Vec<Character>,
+    pub score: Score,
+    pub game_over: bool,
+    /// The game ended because the board is full.
+    pub victory: bool,
+    pub rules: Rules,
+    /// Attempts of the player, for each journey or for the whole game
+    /// depending on the rules.
+    pub attempts: u32,
+    /// Steps played since the start of the game, blocked moves excluded.
+    pub turn: u32,
+    next_id: CharacterId,
+}
+
+impl Simulation {
+    pub fn new(level: &Level) -> Self {
+        let bots: Vec<Character> = level
+            .bots
+            .iter()
+            .zip(0..)
+            .map(|(path, id)| Character {
+                path: path.clone(),
+                ..Character::new(id, path[0], path[0])
+            })
+            .collect();
+
+        Self {
+            grid_size: level.size,
+            walls: level.walls.clone(),
+            fixed_journeys: level.journeys.iter().copied().collect(),
+            fixed_only: level.fixed_only,
+            bot_behaviors: vec![BotBehavior::Loop],
+            player: None,
+            next_id: bots.len() as CharacterId,
+            bots,
+            score: Score::default(),
+            game_over: false,
+            victory: false,
+            rules: Rules::default(),
+            attempts: NB_ATTEMPTS,
+            turn: 0,
+        }
+    }
+
+    pub fn character(&self, id: CharacterId) -> Option<&Character> {
+        self.player
+            .iter()
+            .chain(self.bots.iter())
+            .find(|character| character.id == id)
+    }
+
+    pub fn is_player(&self, id: CharacterId) -> bool {
+        self.player.as_ref().is_some_and(|player| player.id == id)
+    }
+
+    /// Spawns a new player on the next journey of the level if its start is
+    /// free, otherwise on a free border cell with a target on another side.
+    ///
+    /// Only journeys with a safe route to the target and back through the
+    /// current bots within [`SPAWN_SEARCH_TURNS`] are offered, the game is over
+    /// when there is none left.
+    /// Levels with fixed journeys only play them as they are, in order.
+    fn spawn_player(&mut self, rng: &mut impl Rng) -> Option<CharacterId> {
+        if self.fixed_only {
+            let (start_pos, target_pos) = self.fixed_journeys.pop_front()?;
+            return Some(self.add_player(start_pos, target_pos, rng));
+        }
+
+        let avoid_positions = self.occupied_positions();
+        let fixed_journey = self.fixed_journeys.pop_front();
+
+        let time_grid = TimeGrid::with_max_turns(self, SPAWN_SEARCH_TURNS);
+        let mut safe_targets = HashMap::new();
+        let mut is_safe = |start_pos: IVec2, target_pos: IVec2| {
+            safe_targets
+                .entry(start_pos)
+                .or_insert_with(|| time_grid.journey_targets(start_pos))
+                .contains(&target_pos)
+        };
+
+        let fixed_journey = fixed_journey.filter(|&(start_pos, target_pos)| {
+            !avoid_positions.contains(&start_pos) && is_safe(start_pos, target_pos)
+        });
+        let (start_pos, target_pos) = match fixed_journey {
+            Some(journey) => journey,
+            None => rand_journey_target(
+                self.grid_size,
+                &avoid_positions,
+                &self.walls,
+                &mut is_safe,
+                rng,
+            )?,
+        };
+
+        Some(self.add_player(start_pos, target_pos, rng))
+    }
+
+    /// Cells where a journey cannot start.
+    fn occupied_positions(&self) -> Vec<IVec2> {
+        self.bots
+            .iter()
+            .flat_map(|bot| [bot.pos, bot.start_pos])
+            .chain(self.walls.iter().copied())
+            .collect()
+    }
+
+    /// No journey can start anymore, safe or not. The journeys of levels
+    /// playing only theirs are all done.
+    fn is_full(&self) -> bool {
+        if self.fixed_only {
+            return true;
+        }
+        let avoid_positions = self.occupied_positions();
+        let side_targets: Vec<IVec2> = SIDES
+            .iter()
+            .flat_map(|side| side.cells(self.grid_size))
+            .filter(|target_pos| !self.walls.contains(target_pos))
+            .collect();
+        !(0..self.grid_size.y as i32)
+            .flat_map(|y| (0..self.grid_size.x as i32).map(move |x| IVec2::new(x, y)))
+            .filter(|start_pos| !avoid_positions.contains(start_pos))
+            .any(|start_pos| {
+                side_targets
+                    .iter()
+                    .any(|&target_pos| target_pos != start_pos)
+            })
+    }
+
+    /// The scale is drawn here rather than by the frontend, so a seed spawns
+    /// the same journeys with or without a window.
+    fn add_player(
+        &mut self,
+        start_pos: IVec2,
+        target_pos: IVec2,
+        rng: &mut impl Rng,
+    ) -> CharacterId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.player = Some(Character {
+            scale: rng.gen_range(0.6..0.9),
+            ..Character::new(id, start_pos, target_pos)
+        });
+        id
+    }
+
+    /// Next move of a safe shortest route for the player, counted as a hint
+    /// used unless `max_hints` are already used or there is no such route.
+    pub fn hint(&mut self, max_hints: Option<u32>) -> Option<IVec2> {
+        if max_hints.is_some_and(|max_hints| self.score.hints_used >= max_hints) {
+            return None;
+        }
+        let player = self.player.as_ref()?;
+        let direction = TimeGrid::new(self).next_safe_move(player)?;
+        self.score.hints_used += 1;
+        Some(direction)
+    }
+
+    /// Moves the player one cell, or keeps it in place when it waits, then
+    /// every bot one step along its loop.
+    ///
+    /// Collisions are resolved first: a player hit on the move that would reach
+    /// its target or finish its journey does not get it.
+    pub fn step(&mut self, direction: IVec2, rng: &mut impl Rng) -> Vec<Outcome> {
+        let mut outcomes = Vec::new();
+        if self.game_over {
+            return outcomes;
+        }
+        let grid_size = self.grid_size.as_ivec2();
+        let Some(player) = self.player.as_mut() else {
+            return outcomes;
+        };
+
+        let next_pos = (player.pos + direction).clamp(IVec2::ZERO, grid_size - 1);
+        if direction != WAIT && (next_pos == player.pos || self.walls.contains(&next_pos)) {
+            outcomes.push(Outcome::Blocked);
+            return outcomes;
+        }
+
+        self.turn += 1;
+        let already_reached_target = player.has_reached_target() || player.pos == player.target_pos;
+        player.path.push(player.pos);
+        outcomes.push(Outcome::Moved {
+            id: player.id,
+            from: player.pos,
+            to: next_pos,
+        });
+        player.pos = next_pos;
+
+        move_bots(&mut self.bots, &mut outcomes);
+
+        let collided = collisions(self.player.as_ref(), &self.bots, &outcomes);
+        let player_collided = collided.iter().any(|(id, _)| self.is_player(*id));
+        self.send_back(collided, &mut outcomes);
+        if player_collided {
+            return outcomes;
+        }
+
+        let player = self.player.as_ref().expect("Player");
+        if player.journey_finished() {
+            outcomes.push(Outcome::JourneyFinished { id: player.id });
+            self.finish_journey(rng, &mut outcomes);
+        } else if !already_reached_target && player.pos == player.target_pos {
+            outcomes.push(Outcome::TargetReached { id: player.id });
+        }
+
+        outcomes
+    }
+
+    fn finish_journey(&mut self, rng: &mut impl Rng, outcomes: &mut Vec<Outcome>) {
+        let mut bot = self.player.take().expect("Player");
+        bot.bot_index = 0;
+        bot.bot_phase = 0;
+        // Only drawn when there is a choice, so classic games keep their seeds
+        if self.bot_behaviors.len() > 1 {
+            bot.behavior = *self.bot_behaviors.choose(rng).expect("Bot behaviors");
+        } else if let Some(&behavior) = self.bot_behaviors.first() {
+            bot.behavior = behavior;
+        }
+        self.bots.push(bot);
+        self.score.nb_journeys += 1;
+
+        match self.spawn_player(rng) {
+            Some(id) => {
+                if self.rules == Rules::AttemptsPerJourney {
+                    self.score.remaining_attempts = self.attempts;
+                }
+                outcomes.push(Outcome::Spawned { id });
+            }
+            None => self.end_without_player(outcomes),
+        }
+    }
+
+    /// Spawns the first player, or ends the game at once when the board has
+    /// no journey to offer.
+    pub fn start(&mut self, rng: &mut impl Rng) -> Vec<Outcome> {
+        let mut outcomes = Vec::new();
+        match self.spawn_player(rng) {
+            Some(id) => outcomes.push(Outcome::Spawned { id }),
+            None => self.end_without_player(&mut outcomes),
+        }
+        outcomes
+    }
+
+    /// Only a full board is a victory, a board whose journeys are all unsafe
+    /// just ends the game.
+    fn end_without_player(&mut self, outcomes: &mut Vec<Outcome>) {
+        self.game_over = true;
+        if self.is_full() {
+            self.victory = true;
+            self.score.bonus += VICTORY_BONUS;
+            outcomes.push(Outcome::Victory);
+        } else {
+            outcomes.push(Outcome::NoSafeJourney);
+        }
+    }
+
+    fn send_back(&mut self, destroyed: Vec<(CharacterId, bool)>, outcomes: &mut Vec<Outcome>) {
+        for (id, head_on) in destroyed {
+            if self.is_player(id) {
+                let player = self.player.as_mut().expect("Player");
+                outcomes.push(Outcome::Collided {
+                    id,
+                    at: player.pos,
+                    head_on,
+                });
+
+                self.score.attempts_used += 1;
+                if self.rules.counts_attempts() {
+                    self.score.remaining_attempts -= 1;
+                }
+                if self.score.remaining_attempts == 0 {
+                    self.game_over = true;
+                    outcomes.push(Outcome::GameOver);
+                    return;
+                }
+                player.respawn();
+                player.path.clear();
+            } else {
+                let bot = self.bots.iter_mut().find(|bot| bot.id == id).expect("Bot");
+                outcomes.push(Outcome::Collided {
+                    id,
+                    at: bot.pos,
+                    head_on,
+                });
+                bot.respawn();
+            }
+        }
+    }
+}
+
+/// Plays one turn of the bots alone. They never depend on the player, which
+/// is always the one sent back when it hits a bot.
+pub fn step_bots(bots: &mut [Character]) -> Vec<Outcome> {
+    let mut outcomes = Vec::new();
+    move_bots(bots, &mut outcomes);
+
+    let collided = collisions(None, bots, &outcomes);
+    for (id, head_on) in collided {
+        let bot = bots.iter_mut().find(|bot| bot.id == id).expect("Bot");
+        outcomes.push(Outcome::Collided {
+            id,
+            at: bot.pos,
+            head_on,
+        });
+        bot.respawn();
+    }
+    outcomes
+}
+
+fn move_bots(bots: &mut [Character], outcomes: &mut Vec<Outcome>) {
+    for bot in bots.iter_mut() {
+        bot.bot_phase = bot.behavior.phase_after(bot.bot_phase, 1, bot.path.len());
+        bot.bot_index = bot.behavior.path_index(bot.bot_phase, bot.path.len());
+        let from = bot.pos;
+        bot.pos = bot.path[bot.bot_index];
+        outcomes.push(Outcome::Moved {
+            id: bot.id,
+            from,
+            to: bot.pos,
+        });
+    }
+}
+
+/// Characters ending on the same cell or swapping their cells collide. The
+/// player is always the one sent back, between two bots the most recent one is.
+fn collisions(
+    player: Option<&Character>,
+    bots: &[Character],
+    outcomes: &[Outcome],
+) -> Vec<(CharacterId, bool)> {
+    let previous_pos = |character: &Character| {
+        outcomes.iter().find_map(|outcome| match outcome {
+            Outcome::Moved { id, from, .. } if *id == character.id => Some(*from),
+            _ => None,
+        })
+    };
+    let is_player = |character: &Character| player.is_some_and(|player| player.id == character.id);
+
+    let mut destroyed: Vec<(CharacterId, bool)> = Vec::new();
+    let characters: Vec<&Character> = player.into_iter().chain(bots.iter()).collect();
+
+    for (i, a) in characters.iter().enumerate() {
+        for b in characters[i + 1..].iter() {
+            // Only neighbours can swap, checked first as it is much cheaper
+            let head_on = a.pos.distance_squared(b.pos) == 1
+                && previous_pos(a) == Some(b.pos)
+                && previous_pos(b) == Some(a.pos);
+            if a.pos != b.pos && !head_on {
+                continue;
+            }
+            let destroyed_id = if is_player(a) { a.id } else { b.id };
+            if !destroyed.iter().any(|(id, _)| *id == destroyed_id) {
+                destroyed.push((destroyed_id, head_on));
+            }
+        }
+    }
+    destroyed
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Top,
+    Down,
+    Left,
+    Right,
+}
+
+const SIDES: [Side; 4] = [Side::Top, Side::Down, Side::Left, Side::Right];
+
+impl Side {
+    /// Cells along this side of the grid, corners excluded.
+    fn cells(self, grid_size: UVec2) -> Vec<IVec2> {
+        let (width, height) = (grid_size.x as i32, grid_size.y as i32);
+        match self {
+            Self::Top => (1..width - 1).map(|x| IVec2::new(x, height - 1)).collect(),
+            Self::Down => (1..width - 1).map(|x| IVec2::new(x, 0)).collect(),
+            Self::Left => (1..height - 1).map(|y| IVec2::new(0, y)).collect(),
+            Self::Right => (1..height - 1).map(|y| IVec2::new(width - 1, y)).collect(),
+        }
+    }
+}
+
+/// Picks uniformly among the free journeys from one side of the grid to
+/// another. Once there is none, the start can be any free cell of the grid.
+fn rand_journey_target(
+    grid_size: UVec2,
+    avoid_positions: &[IVec2],
+    walls: &HashSet<IVec2>,
+    is_safe: &mut impl FnMut(IVec2, IVec2) -> bool,
+    rng: &mut impl Rng,
+) -> Option<(IVec2, IVec2)> {
+    let is_free_journey = |&(start_pos, target_pos): &(IVec2, IVec2)| {
+        !avoid_positions.contains(&start_pos)
+            && !walls.contains(&target_pos)
+            && start_pos != target_pos
+    };
+
+    let mut side_journeys: Vec<(IVec2, IVec2)> = SIDES
+        .iter()
+        .flat_map(|&start_side| {
+            SIDES
+                .iter()
+                .filter(move |&&target_side| target_side != start_side)
+                .flat_map(move |&target_side| {
+                    let targets = target_side.cells(grid_size);
+                    start_side
+                        .cells(grid_size)
+                        .into_iter()
+                        .flat_map(move |start_pos| {
+                            targets
+                                .clone()
+                                .into_iter()
+                                .map(move |target_pos| (start_pos, target_pos))
+                        })
+                })
+        })
+        .filter(is_free_journey)
+        .collect();
+
+    let side_targets: Vec<IVec2> = SIDES
+        .iter()
+        .flat_map(|side| side.cells(grid_size))
+        .collect();
+    let mut fallback_journeys: Vec<(IVec2, IVec2)> = (0..grid_size.y as i32)
+        .flat_map(|y| (0..grid_size.x as i32).map(move |x| IVec2::new(x, y)))
+        .flat_map(|start_pos| {
+            side_targets
+                .iter()
+                .map(move |&target_pos| (start_pos, target_pos))
+        })
+        .filter(is_free_journey)
+        .collect();
+
+    side_journeys.shuffle(rng);
+    fallback_journeys.shuffle(rng);
+    side_journeys
+        .into_iter()
+        .chain(fallback_journeys)
+        .find(|&(start_pos, target_pos)| is_safe(start_pos, target_pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const LEFT: IVec2 = IVec2::NEG_X;
+    const RIGHT: IVec2 = IVec2::X;
+    const UP: IVec2 = IVec2::Y;
+
+    /// Game on `map` playing only its journeys, with its first player spawned.
+    fn start(map: &str) -> (Simulation, StdRng) {
+        let level = Level {
+            fixed_only: true,
+            ..Level::parse(map).expect("Level")
+        };
+        let mut simulation = Simulation::new(&level);
+        let mut rng = StdRng::seed_from_u64(0);
+        assert!(matches!(
+            simulation.start(&mut rng)[..],
+            [Outcome::Spawned { .. }]
+        ));
+        (simulation, rng)
+    }
+
+    fn player(simulation: &Simulation) -> &Character {
+        simulation.player.as_ref().expect("Player")
+    }
+
+    #[test]
+    fn walls_and_edges_block_the_player() {
+        let (mut simulation, mut rng) = start("#A..\n....\n....\na...");
+        let start_pos = player(&simulation).pos;
+
+        assert_eq!(simulation.step(LEFT, &mut rng), [Outcome::Blocked]);
+        assert_eq!(simulation.step(UP, &mut rng), [Outcome::Blocked]);
+        assert_eq!(player(&simulation).pos, start_pos);
+        assert_eq!(simulation.turn, 0);
+    }
+
+    #[test]
+    fn waiting_plays_a_turn_in_place() {
+        let (mut simulation, mut rng) = start("#A..\n....\n....\na...");
+        let id = player(&simulation).id;
+        let start_pos = player(&simulation).pos;
+
+        let outcomes = simulation.step(WAIT, &mut rng);
+        assert_eq!(
+            outcomes,
+            [Outcome::Moved {
+                id,
+                from: start_pos,
+                to: start_pos,
+            }]
+        );
+        assert_eq!(simulation.turn, 1);
+    }
+
+    #[test]
+    fn player_hit_by_a_bot_is_sent_back() {
+        let (mut simulation, mut rng) = start("A.1.\na...\nbot 1 LR");
+        let id = player(&simulation).id;
+
+        // The bot comes and goes while the player waits on its start
+        simulation.step(WAIT, &mut rng);
+        simulation.step(WAIT, &mut rng);
+        let outcomes = simulation.step(RIGHT, &mut rng);
+
+        assert!(outcomes.contains(&Outcome::Collided {
+            id,
+            at: IVec2::new(1, 1),
+            head_on: false,
+        }));
+        let player = player(&simulation);
+        assert_eq!(player.pos, player.start_pos);
+        assert!(player.path.is_empty());
+        assert_eq!(simulation.score.attempts_used, 1);
+        assert_eq!(simulation.score.remaining_attempts, NB_ATTEMPTS - 1);
+        assert!(!simulation.game_over);
+    }
+
+    #[test]
+    fn player_can_be_hit_on_its_first_move() {
+        let (mut simulation, mut rng) = start("A.1.\na...\nbot 1 LR");
+        let id = player(&simulation).id;
+
+        let outcomes = simulation.step(RIGHT, &mut rng);
+
+        assert!(outcomes.contains(&Outcome::Collided {
+            id,
+            at: IVec2::new(1, 1),
+            head_on: false,
+        }));
+        assert_eq!(player(&simulation).pos, player(&simulation).start_pos);
+        assert_eq!(simulation.score.attempts_used, 1);
+    }
+
+    #[test]
+    fn target_is_reached_before_the_journey_finishes() {
+        let (mut simulation, mut rng) = start("A.a.\n....\nB..b");
+        let id = player(&simulation).id;
+
+        assert!(!simulation
+            .step(RIGHT, &mut rng)
+            .contains(&Outcome::TargetReached { id }));
+        assert_eq!(
+            simulation.step(RIGHT, &mut rng).last(),
+            Some(&Outcome::TargetReached { id })
+        );
+        // Reaching the target again is not reported twice
+        let outcomes = simulation.step(LEFT, &mut rng);
+        assert!(!outcomes.contains(&Outcome::TargetReached { id }));
+        assert!(!outcomes.contains(&Outcome::JourneyFinished { id }));
+
+        let outcomes = simulation.step(LEFT, &mut rng);
+        let finished = outcomes
+            .iter()
+            .position(|outcome| *outcome == Outcome::JourneyFinished { id });
+        let spawned = outcomes
+            .iter()
+            .position(|outcome| matches!(outcome, Outcome::Spawned { .. }));
+        assert!(finished.is_some() && finished < spawned);
+        assert_eq!(simulation.score.nb_journeys, 1);
+        assert!(simulation.bots.iter().any(|bot| bot.id == id));
+        assert_eq!(player(&simulation).start_pos, IVec2::new(0, 0));
+    }
+
+    #[test]
+    fn last_attempt_ends_the_game() {
+        let (mut simulation, mut rng) = start("A.1.\na...\nbot 1 LR");
+        simulation.rules = Rules::Lives;
+        simulation.score.remaining_attempts = 1;
+
+        simulation.step(WAIT, &mut rng);
+        simulation.step(WAIT, &mut rng);
+        let outcomes = simulation.step(RIGHT, &mut rng);
+
+        assert_eq!(outcomes.last(), Some(&Outcome::GameOver));
+        assert!(simulation.game_over);
+        assert!(!simulation.victory);
+        assert_eq!(simulation.score.remaining_attempts, 0);
+        assert!(simulation.step(WAIT, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn unlimited_attempts_never_end_the_game() {
+        let (mut simulation, mut rng) = start("A.1.\na...\nbot 1 LR");
+        simulation.rules = Rules::Unlimited;
+        simulation.score.remaining_attempts = 1;
+
+        simulation.step(WAIT, &mut rng);
+        simulation.step(WAIT, &mut rng);
+        simulation.step(RIGHT, &mut rng);
+
+        assert!(!simulation.game_over);
+        assert_eq!(simulation.score.attempts_used, 1);
+    }
+
+    #[test]
+    fn finishing_the_last_journey_is_a_victory() {
+        let (mut simulation, mut rng) = start("A.a.\n....");
+        let id = player(&simulation).id;
+
+        for direction in [RIGHT, RIGHT, LEFT] {
+            simulation.step(direction, &mut rng);
+        }
+        let outcomes = simulation.step(LEFT, &mut rng);
+
+        assert!(outcomes.ends_with(&[Outcome::JourneyFinished { id }, Outcome::Victory]));
+        assert!(simulation.game_over && simulation.victory);
+        assert!(simulation.player.is_none());
+        assert_eq!(simulation.score.nb_journeys, 1);
+        assert_eq!(simulation.score.bonus, VICTORY_BONUS);
+        assert!(simulation.step(WAIT, &mut rng).is_empty());
+    }
+
+    #[test]
+    fn full_board_is_won_at_once() {
+        let level = Level::parse("##\n#.").expect("Level");
+        let mut simulation = Simulation::new(&level);
+        let mut rng = StdRng::seed_from_u64(0);
+
+        assert_eq!(simulation.start(&mut rng), [Outcome::Victory]);
+        assert!(simulation.game_over && simulation.victory);
+        assert_eq!(simulation.score.bonus, VICTORY_BONUS);
+    }
+
+    #[test]
+    fn board_without_a_safe_journey_ends_without_victory() {
+        // Two free side cells, walled in so no route joins them
+        let level = Level::parse("#.##\n####\n####\n##.#").expect("Level");
+        let mut simulation = Simulation::new(&level);
+        let mut rng = StdRng::seed_from_u64(0);
+
+        assert_eq!(simulation.start(&mut rng), [Outcome::NoSafeJourney]);
+        assert!(simulation.game_over && !simulation.victory);
+        assert_eq!(simulation.score.bonus, 0);
+    }
+
+    /// Board of `size` with `nb_bots` going back and forth along random walks.
+    fn crowded(size: UVec2, nb_bots: usize, rng: &mut StdRng) -> Simulation {
+        let max_pos = size.as_ivec2() - 1;
+        let bots = (0..nb_bots)
+            .map(|_| {
+                let mut pos =
+                    IVec2::new(rng.gen_range(0..=max_pos.x), rng.gen_range(0..=max_pos.y));
+                let mut path = vec![pos];
+                for _ in 0..rng.gen_range(4..20) {
+                    let direction = *crate::routes::DIRECTIONS.choose(rng).expect("Direction");
+                    let next_pos = (pos + direction).clamp(IVec2::ZERO, max_pos);
+                    if next_pos != pos {
+                        pos = next_pos;
+                        path.push(pos);
+                    }
+                }
+                // Back to the start, which is not repeated
+                let back: Vec<IVec2> = path.iter().skip(1).rev().skip(1).copied().collect();
+                path.extend(back);
+                path
+            })
+            .collect();
+        Simulation::new(&Level {
+            bots,
+            ..Level::empty(size)
+        })
+    }
+
+    #[test]
+    fn spawning_on_a_crowded_board_searches_a_bounded_time() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut simulation = crowded(UVec2::new(20, 20), 70, &mut rng);
+
+        // The bots only come back to their first state after the horizon
+        let time_grid = TimeGrid::with_max_turns(&simulation, SPAWN_SEARCH_TURNS);
+        assert_eq!(time_grid.turns(), SPAWN_SEARCH_TURNS);
+
+        let outcomes = simulation.start(&mut rng);
+        assert!(matches!(outcomes[..], [Outcome::Spawned { .. }]));
+        assert!(time_grid.next_safe_move(player(&simulation)).is_some());
+    }
+}
@@ -1,16 +1,35 @@
+use bevy::audio::Volume;
 use bevy::prelude::*;
 
 use rand::seq::SliceRandom;
+use rand::Rng;
 
-pub fn play_sound(sound: &Handle<AudioSource>) -> AudioSourceBundle {
+pub fn play_sound(sound: &Handle<AudioSource>, volume: Volume) -> AudioSourceBundle {
     AudioBundle {
         source: sound.clone(),
-        settings: PlaybackSettings::DESPAWN,
+        settings: PlaybackSettings::DESPAWN.with_volume(volume),
     }
 }
 
-pub fn play_random_sound(sounds: &Vec<Handle<AudioSource>>) -> AudioSourceBundle {
-    let mut rng = rand::thread_rng();
-    let sound = sounds.choose(&mut rng).expect("Asset sound");
-    play_sound(sound)
+/// The goal sound lower and the coin one higher, played together for filling
+/// the board in place of the coin of the last journey.
+pub fn play_victory_sounds(
+    goal_sound: &Handle<AudioSource>,
+    coin_sound: &Handle<AudioSource>,
+    volume: Volume,
+) -> [AudioSourceBundle; 2] {
+    let mut goal = play_sound(goal_sound, volume);
+    goal.settings.speed = 0.75;
+    let mut coin = play_sound(coin_sound, volume);
+    coin.settings.speed = 1.5;
+    [goal, coin]
+}
+
+pub fn play_random_sound(
+    sounds: &[Handle<AudioSource>],
+    volume: Volume,
+    rng: &mut impl Rng,
+) -> AudioSourceBundle {
+    let sound = sounds.choose(rng).expect("Asset sound");
+    play_sound(sound, volume)
 }
@@ -0,0 +1,191 @@
+//! Files kept between sessions in the data directory of the platform, there
+//! is none on the web.
+
+use bevy::log::warn;
+use std::io::ErrorKind;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::str::Lines;
+
+const DATA_DIR: &str = "road-on-road";
+
+pub fn data_path(file_name: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(DATA_DIR).join(file_name))
+}
+
+/// Writes `text` to `path`, creating its directory first.
+pub fn write(path: &Path, text: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, text)
+}
+
+/// A small text file of the data directory, starting with a header naming
+/// what it holds and the version of its format, like
+/// `road-on-road settings 2`.
+#[derive(Debug, Clone)]
+pub struct VersionedFile {
+    /// What the file holds, in its header and messages.
+    pub name: &'static str,
+    pub file_name: &'static str,
+    /// Versions still read, the last one is written.
+    pub versions: RangeInclusive<u32>,
+}
+
+impl VersionedFile {
+    fn path(&self) -> Option<PathBuf> {
+        data_path(self.file_name)
+    }
+
+    fn header(&self) -> String {
+        format!("{DATA_DIR} {}", self.name)
+    }
+
+    /// Text of the file, `body` following the header of the current version.
+    pub fn encode(&self, body: &str) -> String {
+        format!("{} {}\n{body}", self.header(), self.versions.end())
+    }
+
+    /// Version of `text` and its lines after the header.
+    pub fn decode<'a>(&self, text: &'a str) -> Result<(u32, Lines<'a>), String> {
+        let mut lines = text.lines();
+        let version = lines
+            .next()
+            .and_then(|line| line.strip_prefix(&self.header()))
+            .ok_or(format!("Not a {} file", self.name))?;
+        match version.trim().parse() {
+            Ok(version) if self.versions.contains(&version) => Ok((version, lines)),
+            _ => Err(format!(
+                "Unsupported {} version {}",
+                self.name,
+                version.trim()
+            )),
+        }
+    }
+
+    /// Reads the file with `decode`, `None` if there is none yet or it cannot
+    /// be read. Also gives where to save it, if anywhere.
+    ///
+    /// An unreadable file is moved aside to a `.bak` file rather than lost on
+    /// the next save, and never saved over if that fails.
+    pub fn load<T>(
+        &self,
+        decode: impl FnOnce(&str) -> Result<T, String>,
+    ) -> (Option<PathBuf>, Option<T>) {
+        match self.path() {
+            Some(path) => self.load_at(path, decode),
+            None => (None, None),
+        }
+    }
+
+    fn load_at<T>(
+        &self,
+        path: PathBuf,
+        decode: impl FnOnce(&str) -> Result<T, String>,
+    ) -> (Option<PathBuf>, Option<T>) {
+        // Files that exist but cannot be read are kept aside like bad ones
+        let err = match std::fs::read_to_string(&path) {
+            Err(err) if err.kind() == ErrorKind::NotFound => return (Some(path), None),
+            Err(err) => err.to_string(),
+            Ok(text) => match decode(&text) {
+                Ok(value) => return (Some(path), Some(value)),
+                Err(err) => err,
+            },
+        };
+
+        let mut backup = path.clone().into_os_string();
+        backup.push(".bak");
+        let backup = PathBuf::from(backup);
+        match std::fs::rename(&path, &backup) {
+            Ok(()) => {
+                warn!(
+                    "Cannot read {}: {err}, kept as {}",
+                    self.name,
+                    backup.display()
+                );
+                (Some(path), None)
+            }
+            Err(rename_err) => {
+                warn!(
+                    "Cannot read {}: {err}, not saved over as it cannot be kept: {rename_err}",
+                    self.name
+                );
+                (None, None)
+            }
+        }
+    }
+
+    pub fn save(&self, path: Option<&Path>, text: &str) {
+        let Some(path) = path else {
+            return;
+        };
+        if let Err(err) = write(path, text) {
+            warn!("Cannot save {} to {}: {err}", self.name, path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: VersionedFile = VersionedFile {
+        name: "test results",
+        file_name: "test.txt",
+        versions: 2..=3,
+    };
+
+    #[test]
+    fn versions_in_range_are_read() {
+        let text = FILE.encode("a\nb\n");
+        assert_eq!(text, "road-on-road test results 3\na\nb\n");
+
+        let (version, lines) = FILE.decode(&text).expect("Current version");
+        assert_eq!(version, 3);
+        assert_eq!(lines.collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(
+            FILE.decode("road-on-road test results 2")
+                .map(|(version, _)| version),
+            Ok(2)
+        );
+    }
+
+    #[test]
+    fn unreadable_file_is_kept_aside() {
+        let dir = std::env::temp_dir().join(format!("road-on-road-{}", std::process::id()));
+        let path = dir.join(FILE.file_name);
+        write(&path, "road-on-road test results 9\nfrom a newer game\n").expect("Write");
+
+        let (save_path, value) = FILE.load_at(path.clone(), |text| FILE.decode(text).map(|_| ()));
+        assert_eq!(value, None);
+        assert_eq!(save_path, Some(path.clone()));
+        assert!(!path.exists());
+        let backup = std::fs::read_to_string(dir.join("test.txt.bak")).expect("Backup");
+        assert!(backup.ends_with("from a newer game\n"));
+
+        // Not even text
+        std::fs::write(&path, b"road-on-road test results 2\n\xff\xfe\n").expect("Write");
+        let (save_path, value) = FILE.load_at(path.clone(), |text| FILE.decode(text).map(|_| ()));
+        assert_eq!(value, None);
+        assert_eq!(save_path, Some(path.clone()));
+        assert!(!path.exists());
+        let backup = std::fs::read(dir.join("test.txt.bak")).expect("Backup");
+        assert!(backup.ends_with(b"\xff\xfe\n"));
+
+        std::fs::remove_dir_all(dir).expect("Clean up");
+    }
+
+    #[test]
+    fn other_files_and_versions_are_refused() {
+        for text in [
+            "road-on-road test results 1\n",
+            "road-on-road test results 4\n",
+            "road-on-road test results\n",
+            "road-on-road settings 3\n",
+            "",
+        ] {
+            assert!(FILE.decode(text).is_err(), "{text:?}");
+        }
+    }
+}
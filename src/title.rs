@@ -0,0 +1,145 @@
+//! Title screen, where the game starts, and its main menu.
+
+use crate::daily::{DailyChallenge, DailyResults};
+use crate::game_state::GameState;
+use crate::grid::GridConfig;
+use crate::menu::{menu_items, MenuCursor, MenuItem};
+use crate::modes::GameMode;
+use bevy::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TitleOption {
+    Play,
+    Daily,
+    Puzzles,
+    Mode,
+    HighScores,
+    Settings,
+    Quit,
+}
+
+impl TitleOption {
+    /// Options of the menu, a web page cannot be quit.
+    fn all() -> Vec<Self> {
+        let mut all = vec![
+            Self::Play,
+            Self::Daily,
+            Self::Puzzles,
+            Self::Mode,
+            Self::HighScores,
+            Self::Settings,
+        ];
+        if cfg!(not(target_arch = "wasm32")) {
+            all.push(Self::Quit);
+        }
+        all
+    }
+
+    /// `played_today` tells if the daily challenge already has its scored run.
+    fn label(self, mode: GameMode, played_today: bool) -> String {
+        match self {
+            Self::Play => "Play".to_string(),
+            Self::Daily if played_today => "Daily challenge (played)".to_string(),
+            Self::Daily => "Daily challenge".to_string(),
+            Self::Puzzles => "Puzzles".to_string(),
+            Self::Mode => format!("Mode: {}", mode.name()),
+            Self::HighScores => "High scores".to_string(),
+            Self::Settings => "Settings".to_string(),
+            Self::Quit => "Quit".to_string(),
+        }
+    }
+}
+
+pub fn spawn_title_screen(
+    mut commands: Commands,
+    grid: Res<GridConfig>,
+    mode: Res<GameMode>,
+    daily_results: Res<DailyResults>,
+) {
+    commands.spawn((
+        StateScoped(GameState::Title),
+        Text2dBundle {
+            text: Text::from_section(
+                "Road on Road",
+                TextStyle {
+                    font_size: 56.0,
+                    ..default()
+                },
+            ),
+            transform: Transform::from_xyz(0., grid.window_size / 4., 0.),
+            ..default()
+        },
+    ));
+
+    let played_today = daily_results
+        .get(chrono::Local::now().date_naive())
+        .is_some();
+    let labels = TitleOption::all()
+        .into_iter()
+        .map(|option| option.label(*mode, played_today));
+    for item in menu_items(labels, Vec3::ZERO) {
+        commands.spawn((StateScoped(GameState::Title), item));
+    }
+}
+
+pub fn choose_title_option(
+    In(choice): In<Option<usize>>,
+    mut commands: Commands,
+    mut grid: ResMut<GridConfig>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    cursor: Res<MenuCursor>,
+    mut items: Query<(&MenuItem, &mut Text)>,
+    mut mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let options = TitleOption::all();
+
+    // The selected mode also changes with the side arrows
+    let mode_step = match choice.map(|index| options[index]) {
+        Some(TitleOption::Play) => return next_state.set(GameState::InGame),
+        Some(TitleOption::Daily) => {
+            commands.insert_resource(DailyChallenge::today(&mut grid));
+            return next_state.set(GameState::InGame);
+        }
+        Some(TitleOption::Puzzles) => return next_state.set(GameState::Puzzles),
+        Some(TitleOption::HighScores) => return next_state.set(GameState::HighScores),
+        Some(TitleOption::Settings) => return next_state.set(GameState::Settings),
+        Some(TitleOption::Quit) => {
+            exit.send(AppExit::Success);
+            return;
+        }
+        Some(TitleOption::Mode) => 1,
+        None if options[cursor.0] != TitleOption::Mode => return,
+        None if keyboard.just_pressed(KeyCode::ArrowRight) => 1,
+        None if keyboard.just_pressed(KeyCode::ArrowLeft) => GameMode::ALL.len() - 1,
+        None => return,
+    };
+
+    let index = GameMode::ALL.iter().position(|&other| other == *mode);
+    *mode = GameMode::ALL[(index.unwrap_or_default() + mode_step) % GameMode::ALL.len()];
+    for (item, mut text) in items.iter_mut() {
+        if options[item.0] == TitleOption::Mode {
+            text.sections[0].value = TitleOption::Mode.label(*mode, false);
+        }
+    }
+}
+
+pub fn spawn_back_item(mut commands: Commands, grid: Res<GridConfig>) {
+    let bottom = Vec3::new(0., -(grid.window_size / 2. - 2. * grid.cell_size()), 0.);
+
+    for item in menu_items(["Back"], bottom) {
+        commands.spawn((StateScoped(GameState::HighScores), item));
+    }
+}
+
+/// Back to the title screen from the high scores, the only entry being "Back".
+pub fn leave_high_scores(
+    In(choice): In<Option<usize>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if choice.is_some() || keyboard.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Title);
+    }
+}
@@ -1,6 +1,5 @@
 use crate::components::*;
-use crate::inputs::journey_finished;
-use crate::movements::sprite_position_translation;
+use crate::grid::GridConfig;
 use bevy::prelude::*;
 
 #[derive(Component)]
@@ -16,12 +15,13 @@ pub fn spawn_first_tutorial(
     trigger: Trigger<FirstPlayerAdded>,
     mut commands: Commands,
     journeys: Query<&Journey, With<FirstPlayer>>,
+    grid: Res<GridConfig>,
 ) {
     let journey = journeys
         .get(trigger.entity())
         .expect("First player journey");
 
-    let text_position = sprite_position_translation(journey.target_pos.0.as_vec2());
+    let text_position = grid.sprite_position_translation(journey.target_pos.0.as_vec2());
 
     commands.spawn((
         GameObject,
@@ -45,6 +45,7 @@ pub fn validate_first_tutorial(
     mut commands: Commands,
     journeys: Query<(&Journey, &Position), With<FirstPlayer>>,
     mut tutorials: Query<(Entity, &mut Transform, &mut Text), With<Tutorial>>,
+    grid: Res<GridConfig>,
 ) {
     for (tutorial_entity, mut transform, mut text) in tutorials.iter_mut() {
         for (journey, pos) in journeys.iter() {
@@ -56,7 +57,7 @@ pub fn validate_first_tutorial(
             let has_reached_target = *pos == journey.target_pos;
             if has_reached_target {
                 transform.translation = Vec3::from((
-                    sprite_position_translation(journey.start_pos.0.as_vec2()),
+                    grid.sprite_position_translation(journey.start_pos.0.as_vec2()),
                     0.,
                 ));
                 text.sections[0].value = String::from("Go back");
@@ -64,3 +65,9 @@ pub fn validate_first_tutorial(
         }
     }
 }
+
+fn journey_finished(journey: &Journey, current_pos: &Position) -> bool {
+    let has_reached_target = journey.path.contains(&journey.target_pos);
+    let back_to_start = *current_pos == journey.start_pos;
+    has_reached_target && back_to_start
+}